@@ -0,0 +1,240 @@
+use super::error::{Error, Result};
+use super::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// Textual address of an entry, such as `folder:Open directory in/Terminal`
+/// or `file(.rs):Tools/Format`.
+///
+/// The activation type is one of `file(<extension or *>)`, `folder` or
+/// `background`, followed by entry names separated by `/`. Literal `/`, `:`
+/// and `\` characters in names are escaped with a backslash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryAddress {
+    pub entry_type: ActivationType,
+    /// The path to the entry as a list of entry names
+    pub name_path: Vec<String>,
+}
+
+impl CtxEntry {
+    /// Gets an existing entry at the given address.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::find("background:Open directory in/Terminal")?;
+    /// ```
+    pub fn find(address: &str) -> Result<Option<CtxEntry>> {
+        let address: EntryAddress = address.parse()?;
+        CtxEntry::get(&address.name_path, &address.entry_type)
+    }
+
+    /// Gets the entry's address.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    /// assert_eq!(entry.address().to_string(), "folder:Basic entry");
+    /// ```
+    pub fn address(&self) -> EntryAddress {
+        EntryAddress {
+            entry_type: self.entry_type.clone(),
+            name_path: self.name_path.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ActivationType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActivationType::File(ext) => write!(f, "file({ext})"),
+            ActivationType::Folder => write!(f, "folder"),
+            ActivationType::Background => write!(f, "background"),
+        }
+    }
+}
+
+impl FromStr for ActivationType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ActivationType> {
+        match s.to_lowercase().as_str() {
+            "folder" => Ok(ActivationType::Folder),
+            "background" => Ok(ActivationType::Background),
+            t => match t.strip_prefix("file(").and_then(|t| t.strip_suffix(')')) {
+                // Keep the extension's original case.
+                Some(ext) if !ext.is_empty() => {
+                    Ok(ActivationType::File(s[5..s.len() - 1].to_string()))
+                }
+                _ => Err(invalid("unknown activation type")),
+            },
+        }
+    }
+}
+
+impl fmt::Display for EntryAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.entry_type)?;
+
+        for (i, name) in self.name_path.iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+
+            for c in name.chars() {
+                if matches!(c, '/' | ':' | '\\') {
+                    write!(f, "\\")?;
+                }
+                write!(f, "{c}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for EntryAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<EntryAddress> {
+        let (type_str, names) = s
+            .split_once(':')
+            .ok_or_else(|| invalid("address is missing an activation type"))?;
+
+        let entry_type = type_str.parse()?;
+
+        let mut name_path = Vec::new();
+        let mut name = String::new();
+        let mut chars = names.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(escaped) => name.push(escaped),
+                    None => return Err(invalid("address ends with an escape character")),
+                },
+                '/' => name_path.push(std::mem::take(&mut name)),
+                _ => name.push(c),
+            }
+        }
+
+        name_path.push(name);
+
+        if name_path.iter().any(|name| name.is_empty()) {
+            return Err(invalid("address contains an empty entry name"));
+        }
+
+        Ok(EntryAddress {
+            entry_type,
+            name_path,
+        })
+    }
+}
+
+// Activation types are written as `folder`, `background` or `file:.rs`.
+// `file(.rs)` is also accepted when reading, matching addresses.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ActivationType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ActivationType::File(ext) => serializer.serialize_str(&format!("file:{ext}")),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ActivationType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        match s.get(..5) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("file:") && s.len() > 5 => {
+                Ok(ActivationType::File(s[5..].to_string()))
+            }
+            _ => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for EntryAddress {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EntryAddress {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::invalid_value(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> EntryAddress {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_activation_types() {
+        assert_eq!(parse("folder:A").entry_type, ActivationType::Folder);
+        assert_eq!(parse("Background:A").entry_type, ActivationType::Background);
+        assert_eq!(
+            parse("file(*):A").entry_type,
+            ActivationType::File("*".to_string())
+        );
+        assert_eq!(
+            parse("file(.RS):A").entry_type,
+            ActivationType::File(".RS".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_name_path() {
+        let address = parse("folder:Open directory in/Terminal");
+        assert_eq!(address.name_path, ["Open directory in", "Terminal"]);
+    }
+
+    #[test]
+    fn parse_escapes() {
+        let address = parse("file(.rs):Tools\\/Utils/Run\\: now");
+        assert_eq!(address.name_path, ["Tools/Utils", "Run: now"]);
+    }
+
+    #[test]
+    fn invalid_addresses() {
+        for s in [
+            "Open in",
+            "desktop:Open in",
+            "file():A",
+            "folder:",
+            "folder:A//B",
+            "folder:A\\",
+        ] {
+            assert!(s.parse::<EntryAddress>().is_err(), "{s} should be invalid");
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let address = EntryAddress {
+            entry_type: ActivationType::File(".rs".to_string()),
+            name_path: vec!["a/b".to_string(), "c:d".to_string(), "e\\f".to_string()],
+        };
+        let s = address.to_string();
+
+        assert_eq!(s, "file(.rs):a\\/b/c\\:d/e\\\\f");
+        assert_eq!(parse(&s), address);
+    }
+}
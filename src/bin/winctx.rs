@@ -0,0 +1,584 @@
+//! Command-line tool for managing Windows context menu entries.
+//!
+//! Entries are referenced by address (e.g., `folder:Open in/Terminal`).
+//! Pass `--json` for machine-readable output.
+//!
+//! Exit codes:
+//! - 0: success
+//! - 1: other registry error
+//! - 2: invalid command-line usage
+//! - 3: entry or value not found
+//! - 4: entry already exists
+//! - 5: permission denied
+//! - 6: invalid name or value
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use win_ctx::*;
+
+#[derive(Parser)]
+#[command(
+    name = "winctx",
+    version,
+    about = "Manage Windows context menu entries"
+)]
+struct Cli {
+    /// Print output as JSON
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List entries and their children
+    List {
+        /// Activation type to list (e.g., `folder`, `background` or `file(.rs)`)
+        #[arg(long = "type", value_name = "TYPE")]
+        entry_type: Option<ActivationType>,
+        /// Maximum depth to list, where root entries have a depth of 0
+        #[arg(long)]
+        depth: Option<usize>,
+    },
+    /// Show an entry and its children as a tree
+    Show {
+        address: EntryAddress,
+        /// Draw the tree with ASCII characters only
+        #[arg(long)]
+        ascii: bool,
+        /// Maximum line length
+        #[arg(long)]
+        width: Option<usize>,
+    },
+    /// Create an entry
+    Add {
+        address: EntryAddress,
+        /// Visible label, if different from the entry's name
+        #[arg(long)]
+        label: Option<String>,
+        #[command(flatten)]
+        props: Props,
+    },
+    /// Change an entry's properties
+    Set {
+        address: EntryAddress,
+        /// Visible label ("" to show the entry's name)
+        #[arg(long)]
+        label: Option<String>,
+        #[command(flatten)]
+        props: Props,
+    },
+    /// Rename an entry
+    Rename {
+        address: EntryAddress,
+        new_name: String,
+        /// How to handle an existing entry with the new name
+        #[arg(long, value_enum, default_value_t = Conflict::Fail)]
+        conflict: Conflict,
+    },
+    /// Delete an entry and its children
+    Rm { address: EntryAddress },
+    /// Export entries and their children as JSON
+    Export {
+        #[arg(required = true)]
+        addresses: Vec<EntryAddress>,
+        /// File to write to instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import entries exported with `export`, undoing them all if one fails
+    Import { file: PathBuf },
+    /// Find entries whose command runs a missing executable
+    Orphans {
+        /// Activation type to scan (e.g., `folder`, `background` or `file(.rs)`)
+        #[arg(long = "type", value_name = "TYPE")]
+        entry_type: Option<ActivationType>,
+        /// Skip entries whose address matches a pattern (e.g., `folder:Git*`)
+        #[arg(long, value_name = "PATTERN")]
+        exclude: Vec<String>,
+        /// Delete the entries found
+        #[arg(long, conflicts_with = "disable")]
+        remove: bool,
+        /// Hide the entries found from the context menu
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Enable, disable or check the pre-Windows 11 context menu
+    ClassicMenu {
+        #[arg(value_enum)]
+        action: Toggle,
+        /// Apply to all users instead of the current user
+        #[arg(long)]
+        machine: bool,
+    },
+}
+
+#[derive(Args)]
+struct Props {
+    /// Command to run ("" to remove)
+    #[arg(long)]
+    command: Option<String>,
+    /// Icon to display ("" to remove)
+    #[arg(long)]
+    icon: Option<String>,
+    /// Position in the menu
+    #[arg(long, value_enum)]
+    position: Option<PositionArg>,
+    /// Separators around the entry
+    #[arg(long, value_enum)]
+    separator: Option<SeparatorArg>,
+    /// Only show the entry with Shift+RClick
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    extended: Option<bool>,
+    /// Identifier of the tool managing the entry ("" to remove)
+    #[arg(long)]
+    owner: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Conflict {
+    Fail,
+    Overwrite,
+    Merge,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PositionArg {
+    Top,
+    Bottom,
+    None,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SeparatorArg {
+    Before,
+    After,
+    Both,
+    None,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Toggle {
+    On,
+    Off,
+    Status,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Export {
+    address: String,
+    entry: EntrySnapshot,
+}
+
+#[derive(Serialize)]
+struct Listed {
+    address: String,
+    label: String,
+    depth: usize,
+    command: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Found {
+    address: String,
+    command: String,
+    executable: String,
+}
+
+#[derive(Serialize)]
+struct MenuStatus {
+    user: bool,
+    machine: bool,
+    user_override: bool,
+    enabled: bool,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    set_notifier(Some(Arc::new(ShellNotifier)));
+
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("winctx: {e}");
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+fn exit_code(e: &Error) -> u8 {
+    match e {
+        Error::NotFound { .. } => 3,
+        Error::AlreadyExists { .. } => 4,
+        Error::PermissionDenied { .. } => 5,
+        Error::InvalidName { .. } | Error::InvalidValue { .. } => 6,
+        _ => 1,
+    }
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    match &cli.command {
+        Command::List { entry_type, depth } => {
+            let entry_types = match entry_type {
+                Some(entry_type) => vec![entry_type.clone()],
+                None => ActivationType::all_registered()?,
+            };
+            let mut listed = Vec::new();
+
+            for entry_type in entry_types {
+                let mut walk = CtxEntry::walk_type(&entry_type);
+
+                if let Some(depth) = depth {
+                    walk = walk.max_depth(*depth);
+                }
+
+                for item in walk {
+                    let item = item.and_then(|WalkEntry { entry, depth }| {
+                        let props = entry.properties()?;
+                        Ok(Listed {
+                            address: entry.address().to_string(),
+                            label: props.display_label().to_string(),
+                            depth,
+                            command: props.command,
+                        })
+                    });
+
+                    // Keep listing past keys that fail to read.
+                    match item {
+                        Ok(item) => listed.push(item),
+                        Err(e) => eprintln!("winctx: {e}"),
+                    }
+                }
+            }
+
+            if cli.json {
+                print_json(&listed)?;
+            } else {
+                for item in listed {
+                    println!("{}{}", "  ".repeat(item.depth), item.address);
+                }
+            }
+        }
+        Command::Show {
+            address,
+            ascii,
+            width,
+        } => {
+            let snapshot = find(address)?.snapshot()?;
+
+            if cli.json {
+                print_json(&snapshot)?;
+            } else {
+                print!(
+                    "{}",
+                    snapshot.render(&TreeOptions {
+                        width: *width,
+                        ascii: *ascii,
+                        ..Default::default()
+                    })
+                );
+            }
+        }
+        Command::Add {
+            address,
+            label,
+            props,
+        } => {
+            let opts = EntryOptions {
+                command: non_empty(&props.command),
+                icon: non_empty(&props.icon),
+                position: props.position.and_then(position),
+                separator: props.separator.and_then(separator),
+                extended: props.extended.unwrap_or(false),
+                owner: non_empty(&props.owner),
+                ..Default::default()
+            };
+            let (name, parent_path) = address.name_path.split_last().unwrap();
+
+            let entry = batch(|| -> Result<CtxEntry> {
+                let mut entry = if parent_path.is_empty() {
+                    CtxEntry::new_with_options(name, &address.entry_type, &opts)?
+                } else {
+                    let parent = find(&EntryAddress {
+                        entry_type: address.entry_type.clone(),
+                        name_path: parent_path.to_vec(),
+                    })?;
+                    parent.new_child_with_options(name, &opts)?
+                };
+
+                if let Some(label) = non_empty(label)
+                    && let Err(e) = entry.set_label(Some(&label))
+                {
+                    let _ = entry.delete();
+                    return Err(e);
+                }
+                Ok(entry)
+            })?;
+
+            print_address(cli, &entry)?;
+        }
+        Command::Set {
+            address,
+            label,
+            props,
+        } => {
+            let mut entry = find(address)?;
+
+            batch(|| -> Result<()> {
+                if let Some(label) = label {
+                    entry.set_label(non_empty_str(label))?;
+                }
+                if let Some(command) = &props.command {
+                    entry.set_command(non_empty_str(command))?;
+                }
+                if let Some(icon) = &props.icon {
+                    entry.set_icon(non_empty_str(icon))?;
+                }
+                if let Some(arg) = props.position {
+                    entry.set_position(position(arg))?;
+                }
+                if let Some(arg) = props.separator {
+                    entry.set_separator(separator(arg))?;
+                }
+                if let Some(extended) = props.extended {
+                    entry.set_extended(extended)?;
+                }
+                if let Some(owner) = &props.owner {
+                    entry.set_owner(non_empty_str(owner))?;
+                }
+                Ok(())
+            })?;
+
+            print_address(cli, &entry)?;
+        }
+        Command::Rename {
+            address,
+            new_name,
+            conflict,
+        } => {
+            let mut entry = find(address)?;
+            let opts = RenameOptions {
+                conflict: match conflict {
+                    Conflict::Fail => ConflictPolicy::Fail,
+                    Conflict::Overwrite => ConflictPolicy::Overwrite,
+                    Conflict::Merge => ConflictPolicy::Merge,
+                },
+                update_label: false,
+            };
+
+            entry.rename_with_options(new_name, &opts)?;
+            print_address(cli, &entry)?;
+        }
+        Command::Rm { address } => find(address)?.delete()?,
+        Command::Export { addresses, output } => {
+            let mut exports = Vec::new();
+
+            for address in addresses {
+                exports.push(Export {
+                    address: address.to_string(),
+                    entry: find(address)?.snapshot()?,
+                });
+            }
+
+            let json = serde_json::to_string_pretty(&exports).map_err(invalid)?;
+
+            match output {
+                Some(path) => fs::write(path, json)?,
+                None => println!("{json}"),
+            }
+        }
+        Command::Import { file } => {
+            let json = fs::read_to_string(file)?;
+            let exports: Vec<Export> = serde_json::from_str(&json).map_err(invalid)?;
+            // Imported entries, along with parents given a submenu by them.
+            let mut created: Vec<(CtxEntry, Option<CtxEntry>)> = Vec::new();
+
+            let result = batch(|| -> Result<()> {
+                for export in &exports {
+                    let address: EntryAddress = export.address.parse()?;
+                    let (name, parent_path) = address.name_path.split_last().unwrap();
+                    let mut snapshot = export.entry.clone();
+                    snapshot.name = name.clone();
+
+                    if parent_path.is_empty() {
+                        created.push((snapshot.restore(&address.entry_type)?, None));
+                    } else {
+                        let parent = find(&EntryAddress {
+                            entry_type: address.entry_type.clone(),
+                            name_path: parent_path.to_vec(),
+                        })?;
+                        let had_submenu = parent.get_value("Subcommands")?.is_some();
+                        let entry = snapshot.restore_as_child(&parent)?;
+                        created.push((entry, (!had_submenu).then_some(parent)));
+                    }
+                }
+                Ok(())
+            });
+
+            // Undo the imports made before the failure, most recent first.
+            if let Err(e) = result {
+                batch(|| {
+                    for (entry, parent) in created.into_iter().rev() {
+                        let _ = entry.delete();
+                        if let Some(mut parent) = parent {
+                            let _ = parent.delete_value("Subcommands");
+                        }
+                    }
+                });
+                return Err(e);
+            }
+
+            let imported: Vec<String> = created
+                .iter()
+                .map(|(entry, _)| entry.address().to_string())
+                .collect();
+
+            if cli.json {
+                print_json(&imported)?;
+            } else {
+                for address in imported {
+                    println!("{address}");
+                }
+            }
+        }
+        Command::Orphans {
+            entry_type,
+            exclude,
+            remove,
+            disable,
+        } => {
+            let mut scan = OrphanScan::new();
+
+            if let Some(entry_type) = entry_type {
+                scan = scan.entry_type(entry_type);
+            }
+            for pattern in exclude {
+                scan = scan.exclude(pattern);
+            }
+
+            let orphans = scan.run()?;
+
+            if cli.json {
+                let found: Vec<Found> = orphans
+                    .iter()
+                    .map(|o| Found {
+                        address: o.address.to_string(),
+                        command: o.command.clone(),
+                        executable: o.executable.clone(),
+                    })
+                    .collect();
+                print_json(&found)?;
+            } else {
+                print!("{}", orphan_report(&orphans));
+            }
+
+            batch(|| -> Result<()> {
+                for mut orphan in orphans {
+                    if *remove {
+                        orphan.remove()?;
+                    } else if *disable {
+                        orphan.disable()?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Command::ClassicMenu { action, machine } => {
+            let scope = if *machine {
+                Scope::Machine
+            } else {
+                Scope::User
+            };
+
+            if *action != Toggle::Status {
+                set_classic_menu(*action == Toggle::On, scope)?;
+            }
+
+            let state = classic_menu_state()?;
+
+            if cli.json {
+                print_json(&MenuStatus {
+                    user: state.user,
+                    machine: state.machine,
+                    user_override: state.user_override,
+                    enabled: state.enabled(),
+                })?;
+            } else if *action == Toggle::Status {
+                println!("user: {}", on_off(state.user));
+                println!("machine: {}", on_off(state.machine));
+                println!("enabled: {}", on_off(state.enabled()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn find(address: &EntryAddress) -> Result<CtxEntry> {
+    CtxEntry::get(&address.name_path, &address.entry_type)?.ok_or_else(|| Error::NotFound {
+        context: ErrorContext {
+            operation: Some("finding entry"),
+            path: Some(address.to_string()),
+            value: None,
+        },
+    })
+}
+
+fn invalid(e: impl Display) -> Error {
+    Error::InvalidValue {
+        reason: e.to_string(),
+        context: ErrorContext::default(),
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value.clone().filter(|v| !v.is_empty())
+}
+
+fn non_empty_str(value: &str) -> Option<&str> {
+    Some(value).filter(|v| !v.is_empty())
+}
+
+fn position(arg: PositionArg) -> Option<MenuPosition> {
+    match arg {
+        PositionArg::Top => Some(MenuPosition::Top),
+        PositionArg::Bottom => Some(MenuPosition::Bottom),
+        PositionArg::None => None,
+    }
+}
+
+fn separator(arg: SeparatorArg) -> Option<Separator> {
+    match arg {
+        SeparatorArg::Before => Some(Separator::Before),
+        SeparatorArg::After => Some(Separator::After),
+        SeparatorArg::Both => Some(Separator::Both),
+        SeparatorArg::None => None,
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).map_err(invalid)?;
+    println!("{json}");
+    Ok(())
+}
+
+fn print_address(cli: &Cli, entry: &CtxEntry) -> Result<()> {
+    let address = entry.address().to_string();
+
+    if cli.json {
+        print_json(&serde_json::json!({ "address": address }))
+    } else {
+        println!("{address}");
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use super::entry::{hkcr, validate_name};
+use super::error::{Error, Result};
+use super::notify;
+use super::path::get_full_path;
+use super::*;
+
+/// Builder for an entry and its children, created together by
+/// [`build`](EntryBuilder::build)
+///
+/// # Examples
+///
+/// ```no_run
+/// let built = CtxEntry::builder("Open directory in", &ActivationType::Background)
+///     .icon("C:\\Windows\\System32\\cmd.exe")
+///     .child(|c| c.name("Terminal").command("cmd /s /k pushd \"%V\""))
+///     .child(|c| c.name("Powershell").command("powershell -noexit -command Set-Location -literalPath '%V'"))
+///     .build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct EntryBuilder {
+    pub(crate) name: String,
+    pub(crate) entry_type: ActivationType,
+    pub(crate) label: Option<String>,
+    opts: EntryOptions,
+    children: Vec<EntryBuilder>,
+}
+
+/// Entry created by an [`EntryBuilder`], along with its created children
+#[derive(Debug)]
+pub struct BuiltEntry {
+    pub entry: CtxEntry,
+    /// Created children in the order they were added to the builder
+    pub children: Vec<BuiltEntry>,
+}
+
+impl CtxEntry {
+    /// Starts building a top-level entry with the given name and children.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let built = CtxEntry::builder("Basic entry", &ActivationType::Folder)
+    ///     .command("cmd.exe")
+    ///     .build()?;
+    /// ```
+    pub fn builder(name: &str, entry_type: &ActivationType) -> EntryBuilder {
+        EntryBuilder {
+            name: name.to_string(),
+            entry_type: entry_type.clone(),
+            label: None,
+            opts: EntryOptions::default(),
+            children: Vec::new(),
+        }
+    }
+}
+
+impl EntryBuilder {
+    /// Sets the entry's name.
+    pub fn name(mut self, name: &str) -> EntryBuilder {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Sets the entry's visible label.
+    pub fn label(mut self, label: &str) -> EntryBuilder {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Sets the command to run when the entry is selected.
+    pub fn command(mut self, command: &str) -> EntryBuilder {
+        self.opts.command = Some(command.to_string());
+        self
+    }
+
+    /// Sets the icon to display beside the entry.
+    pub fn icon(mut self, icon: &str) -> EntryBuilder {
+        self.opts.icon = Some(icon.to_string());
+        self
+    }
+
+    /// Sets the entry's position in the context menu.
+    pub fn position(mut self, position: MenuPosition) -> EntryBuilder {
+        self.opts.position = Some(position);
+        self
+    }
+
+    /// Sets the separators to include around the entry.
+    pub fn separator(mut self, separator: Separator) -> EntryBuilder {
+        self.opts.separator = Some(separator);
+        self
+    }
+
+    /// Sets whether the entry should only appear with Shift+RClick.
+    pub fn extended(mut self, extended: bool) -> EntryBuilder {
+        self.opts.extended = extended;
+        self
+    }
+
+    /// Sets the identifier of the tool that created the entry.
+    pub fn owner(mut self, owner: &str) -> EntryBuilder {
+        self.opts.owner = Some(owner.to_string());
+        self
+    }
+
+    /// Adds a label for the given UI language (e.g., `"de-DE"`).
+    pub fn localized_label(mut self, language: &str, label: &str) -> EntryBuilder {
+        self.opts
+            .labels
+            .insert(language.to_string(), label.to_string());
+        self
+    }
+
+    /// Replaces all options at once.
+    pub fn options(mut self, opts: EntryOptions) -> EntryBuilder {
+        self.opts = opts;
+        self
+    }
+
+    /// Adds a child entry, configured by the given function from an
+    /// unnamed builder.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let builder = CtxEntry::builder("Open in", &ActivationType::Folder)
+    ///     .child(|c| c.name("Terminal").command("cmd /s /k pushd \"%V\""));
+    /// ```
+    pub fn child(mut self, f: impl FnOnce(EntryBuilder) -> EntryBuilder) -> EntryBuilder {
+        let child = EntryBuilder {
+            name: String::new(),
+            entry_type: self.entry_type.clone(),
+            label: None,
+            opts: EntryOptions::default(),
+            children: Vec::new(),
+        };
+
+        self.children.push(f(child));
+        self
+    }
+
+    /// Validates the whole tree, then creates the entry and all of its
+    /// children. Nothing is created if validation fails, and any created
+    /// entries are removed if creation fails partway through.
+    ///
+    /// Fails with `AlreadyExists` if the top-level entry already exists.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let built = CtxEntry::builder("Open in", &ActivationType::Folder)
+    ///     .child(|c| c.name("Terminal").command("cmd /s /k pushd \"%V\""))
+    ///     .build()?;
+    /// let terminal = &built.children[0].entry;
+    /// ```
+    pub fn build(&self) -> Result<BuiltEntry> {
+        self.validate(&[])?;
+
+        let name_path = [self.name.clone()];
+
+        if CtxEntry::get(&name_path, &self.entry_type)?.is_some() {
+            let path = get_full_path(&self.entry_type, &name_path);
+            return Err(Error::already_exists().with_context("creating key", &path, None));
+        }
+
+        let _batch = notify::begin_batch();
+
+        // An entry created by someone else since the check is left alone.
+        let created = match CtxEntry::create(&name_path, &self.entry_type, &self.opts) {
+            Err(e @ Error::AlreadyExists { .. }) => return Err(e),
+            created => created,
+        };
+
+        created.and_then(|entry| self.fill(entry)).inspect_err(|_| {
+            let path = get_full_path(&self.entry_type, &name_path);
+            if let Ok(root) = hkcr() {
+                let _ = root.delete_subkey_all(&path);
+            }
+        })
+    }
+
+    // Checks names throughout the tree, reporting the first problem found.
+    fn validate(&self, parent_path: &[String]) -> Result<()> {
+        let mut name_path = parent_path.to_vec();
+        name_path.push(self.name.clone());
+
+        validate_name(&self.name).map_err(|e| {
+            let path = get_full_path(&self.entry_type, &name_path);
+            e.with_context("validating entry", &path, None)
+        })?;
+
+        for (i, child) in self.children.iter().enumerate() {
+            let duplicate = self.children[..i]
+                .iter()
+                .any(|sibling| sibling.name.eq_ignore_ascii_case(&child.name));
+
+            if duplicate {
+                let path = get_full_path(&self.entry_type, &name_path);
+                return Err(
+                    Error::invalid_name(&child.name, "name is used by a sibling").with_context(
+                        "validating entry",
+                        &path,
+                        None,
+                    ),
+                );
+            }
+
+            child.validate(&name_path)?;
+        }
+
+        Ok(())
+    }
+
+    // Sets the label and creates the children of an entry created with
+    // this builder's options.
+    fn fill(&self, mut entry: CtxEntry) -> Result<BuiltEntry> {
+        if self.label.is_some() {
+            entry.set_label(self.label.as_deref())?;
+        }
+
+        let mut children = Vec::new();
+
+        for child in &self.children {
+            let child_entry = entry.new_child_with_options(&child.name, &child.opts)?;
+            children.push(child.fill(child_entry)?);
+        }
+
+        Ok(BuiltEntry { entry, children })
+    }
+}
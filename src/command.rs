@@ -0,0 +1,283 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use winreg::RegKey;
+use winreg::enums::*;
+
+// Registered paths of executables started by name (e.g., `chrome.exe`).
+const APP_PATHS: &str = r"Software\Microsoft\Windows\CurrentVersion\App Paths";
+
+// Extensions that make an unquoted path end, as Windows tries them when
+// splitting a command line on spaces.
+const EXECUTABLE_EXTENSIONS: [&str; 4] = [".exe", ".com", ".bat", ".cmd"];
+
+// Command line split into its executable and arguments.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ParsedCommand<'a> {
+    pub executable: &'a str,
+    pub quoted: bool,
+    pub args: &'a str,
+}
+
+pub(crate) fn parse_command(command: &str) -> ParsedCommand<'_> {
+    let command = command.trim_start();
+
+    if let Some(rest) = command.strip_prefix('"') {
+        let (executable, args) = rest.split_once('"').unwrap_or((rest, ""));
+        return ParsedCommand {
+            executable,
+            quoted: true,
+            args: args.trim_start(),
+        };
+    }
+
+    let end = unquoted_executable_end(command)
+        .or_else(|| command.find(char::is_whitespace))
+        .unwrap_or(command.len());
+
+    ParsedCommand {
+        executable: &command[..end],
+        quoted: false,
+        args: command[end..].trim_start(),
+    }
+}
+
+// Finds the end of an unquoted path to an executable that may contain
+// spaces (e.g., `C:\Program Files\tool.exe --flag`).
+fn unquoted_executable_end(command: &str) -> Option<usize> {
+    let lower = command.to_ascii_lowercase();
+
+    EXECUTABLE_EXTENSIONS
+        .iter()
+        .filter_map(|ext| {
+            lower
+                .match_indices(ext)
+                .map(|(i, _)| i + ext.len())
+                .find(|&end| lower[end..].chars().next().is_none_or(char::is_whitespace))
+        })
+        .min()
+}
+
+// Expands `%NAME%` environment variables, leaving unknown ones as is.
+pub(crate) fn expand_env(s: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = s;
+
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        match after.find('%') {
+            Some(end) if end > 0 => match env::var(&after[..end]) {
+                Ok(value) => {
+                    expanded.push_str(&value);
+                    rest = &after[end + 1..];
+                }
+                Err(_) => {
+                    expanded.push('%');
+                    rest = after;
+                }
+            },
+            _ => {
+                expanded.push('%');
+                rest = after;
+            }
+        }
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+// Gets the executable of a command if it does not exist. Executables that
+// are placeholders or use unknown variables (e.g., `"%1"`) cannot be
+// checked and are treated as existing.
+pub(crate) fn missing_executable(command: &str) -> Option<&str> {
+    let executable = parse_command(command).executable;
+
+    if executable.is_empty() || expand_env(executable).contains('%') {
+        return None;
+    }
+
+    let found = executable_candidates(command)
+        .into_iter()
+        .filter(|c| !expand_env(c).contains('%'))
+        .any(|c| resolve_executable(c).is_some());
+
+    match found {
+        true => None,
+        false => Some(executable),
+    }
+}
+
+// Lists the executables Windows may start for a command, in the order it
+// tries them. An unquoted path is cut at each space in turn, so
+// `C:\Program Files\App\app --x` tries `C:\Program`, then
+// `C:\Program Files\App\app` and so on.
+fn executable_candidates(command: &str) -> Vec<&str> {
+    let parsed = parse_command(command);
+
+    if parsed.quoted {
+        return vec![parsed.executable];
+    }
+
+    let command = command.trim();
+    let mut candidates: Vec<&str> = command
+        .char_indices()
+        .filter(|&(i, c)| c.is_whitespace() && !command[..i].ends_with(char::is_whitespace))
+        .map(|(i, _)| &command[..i])
+        .collect();
+    candidates.push(command);
+
+    candidates
+}
+
+// Resolves an executable like Windows does when starting a process: as a
+// path if it has a directory, otherwise from the system directories,
+// `PATH` and App Paths, trying each `PATHEXT` extension if it has none.
+pub(crate) fn resolve_executable(executable: &str) -> Option<PathBuf> {
+    let executable = expand_env(executable);
+    let path = Path::new(&executable);
+
+    if executable.contains(['\\', '/', ':']) {
+        return with_extensions(path).into_iter().find(|p| p.is_file());
+    }
+
+    let mut dirs = Vec::new();
+
+    if let Ok(root) = env::var("SystemRoot") {
+        dirs.push(Path::new(&root).join("System32"));
+        dirs.push(PathBuf::from(root));
+    }
+    if let Some(path_var) = env::var_os("PATH") {
+        dirs.extend(env::split_paths(&path_var));
+    }
+
+    dirs.iter()
+        .flat_map(|dir| with_extensions(&dir.join(path)))
+        .find(|p| p.is_file())
+        .or_else(|| app_path(&executable))
+}
+
+// Looks up an executable name in App Paths, first for the current user,
+// then for the machine.
+fn app_path(name: &str) -> Option<PathBuf> {
+    let name = match Path::new(name).extension() {
+        Some(_) => name.to_string(),
+        None => format!("{name}.exe"),
+    };
+
+    [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE]
+        .into_iter()
+        .filter_map(|hkey| {
+            let key = RegKey::predef(hkey)
+                .open_subkey(format!("{APP_PATHS}\\{name}"))
+                .ok()?;
+            key.get_value::<String, _>("").ok()
+        })
+        .map(|path| PathBuf::from(expand_env(path.trim_matches('"'))))
+        .find(|path| path.is_file())
+}
+
+// Lists the path followed by the path with each `PATHEXT` extension if
+// it has no extension.
+fn with_extensions(path: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![path.to_path_buf()];
+
+    if path.extension().is_none() {
+        let path_ext = env::var("PATHEXT").unwrap_or_else(|_| EXECUTABLE_EXTENSIONS.join(";"));
+
+        for ext in path_ext.split(';').filter(|e| !e.is_empty()) {
+            let mut with_ext = path.as_os_str().to_os_string();
+            with_ext.push(ext);
+            paths.push(PathBuf::from(with_ext));
+        }
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(command: &str) -> (&str, bool, &str) {
+        let parsed = parse_command(command);
+        (parsed.executable, parsed.quoted, parsed.args)
+    }
+
+    #[test]
+    fn parse_quoted_command() {
+        assert_eq!(
+            parse(r#""C:\Program Files\Tool\tool.exe" "%1""#),
+            (r"C:\Program Files\Tool\tool.exe", true, r#""%1""#)
+        );
+        assert_eq!(parse(r#""tool.exe"#), ("tool.exe", true, ""));
+    }
+
+    #[test]
+    fn parse_unquoted_command() {
+        assert_eq!(
+            parse(r#"cmd /s /k pushd "%V""#),
+            ("cmd", false, r#"/s /k pushd "%V""#)
+        );
+        assert_eq!(
+            parse(r"C:\Program Files\Tool\tool.exe --open %1"),
+            (r"C:\Program Files\Tool\tool.exe", false, "--open %1")
+        );
+        assert_eq!(parse("notepad.exe"), ("notepad.exe", false, ""));
+        assert_eq!(
+            parse(r"C:\tools.exec\a.bat x"),
+            (r"C:\tools.exec\a.bat", false, "x")
+        );
+    }
+
+    #[test]
+    fn unquoted_path_candidates() {
+        assert_eq!(
+            executable_candidates(r"C:\Program Files\App\app  --x"),
+            [
+                r"C:\Program",
+                r"C:\Program Files\App\app",
+                r"C:\Program Files\App\app  --x",
+            ]
+        );
+        assert_eq!(
+            executable_candidates(r#""C:\Program Files\App\app" --x"#),
+            [r"C:\Program Files\App\app"]
+        );
+    }
+
+    #[test]
+    fn missing_unquoted_path_with_spaces() {
+        let dir = env::temp_dir().join(format!("win ctx {}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let app = dir.join("app");
+        std::fs::write(&app, "").unwrap();
+
+        let command = format!("{} --x", app.display());
+        let missing = format!("{} --x", dir.join("missing").display());
+
+        assert_eq!(missing_executable(&command), None);
+        assert_eq!(
+            missing_executable(&missing),
+            Some(parse_command(&missing).executable)
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn expand_env_vars() {
+        let path = env::var("PATH").unwrap();
+        assert_eq!(expand_env("%PATH%\\x"), format!("{path}\\x"));
+        assert_eq!(expand_env("%1 100% %"), "%1 100% %");
+        assert_eq!(expand_env("%WIN_CTX_UNSET_VAR%"), "%WIN_CTX_UNSET_VAR%");
+    }
+
+    #[test]
+    fn resolve_existing_executable() {
+        let exe = env::current_exe().unwrap();
+        assert_eq!(resolve_executable(exe.to_str().unwrap()), Some(exe));
+        assert_eq!(resolve_executable(r"C:\missing\tool.exe"), None);
+        assert_eq!(resolve_executable("win-ctx-missing-tool"), None);
+    }
+}
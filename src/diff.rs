@@ -0,0 +1,643 @@
+use super::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Change to a single property of an entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldChange {
+    Label(Option<String>, Option<String>),
+    Command(Option<String>, Option<String>),
+    Icon(Option<String>, Option<String>),
+    Position(Option<MenuPosition>, Option<MenuPosition>),
+    Separator(Option<Separator>, Option<Separator>),
+    Extended(bool, bool),
+    /// Change to a value not covered by the other fields
+    Value {
+        name: String,
+        before: Option<RegValue>,
+        after: Option<RegValue>,
+    },
+    /// Change to a value in a subkey other than `shell`, where `key` is the
+    /// subkey's path relative to the entry (e.g., `command`)
+    KeyValue {
+        key: String,
+        name: String,
+        before: Option<RegValue>,
+        after: Option<RegValue>,
+    },
+}
+
+/// Change between two entry trees. Paths are lists of entry names relative
+/// to the compared trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryChange {
+    Added {
+        path: Vec<String>,
+    },
+    Removed {
+        path: Vec<String>,
+    },
+    /// Entry renamed. Siblings are only matched as renamed when nothing else
+    /// changed in them or their children, and show as removed and added
+    /// otherwise. The root compared by [`EntrySnapshot::diff`] is always
+    /// matched, so its rename may be followed by changes at its new path.
+    Renamed {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    Modified {
+        path: Vec<String>,
+        changes: Vec<FieldChange>,
+    },
+}
+
+impl EntrySnapshot {
+    /// Compares the snapshot to a later snapshot of the same entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let before = entry.snapshot()?;
+    /// entry.set_icon(Some("C:\\Windows\\System32\\cmd.exe"))?;
+    /// let changes = before.diff(&entry.snapshot()?);
+    /// ```
+    pub fn diff(&self, other: &EntrySnapshot) -> Vec<EntryChange> {
+        let mut changes = Vec::new();
+
+        if self.name != other.name {
+            changes.push(EntryChange::Renamed {
+                from: vec![self.name.clone()],
+                to: vec![other.name.clone()],
+            });
+        }
+
+        diff_entry(&[], self, other, &mut changes);
+        changes
+    }
+}
+
+/// Compares two lists of sibling entries, such as every root entry of
+/// an activation type on two machines.
+///
+/// # Examples
+///
+/// ```no_run
+/// let changes = win_ctx::diff_entries(&before, &after);
+/// println!("{}", win_ctx::diff_report(&changes));
+/// ```
+pub fn diff_entries(before: &[EntrySnapshot], after: &[EntrySnapshot]) -> Vec<EntryChange> {
+    let mut changes = Vec::new();
+    diff_siblings(&[], before, after, &mut changes);
+    changes
+}
+
+/// Formats changes as a human-readable report with one change per line.
+///
+/// # Examples
+///
+/// ```no_run
+/// let report = win_ctx::diff_report(&before.diff(&after));
+/// ```
+pub fn diff_report(changes: &[EntryChange]) -> String {
+    changes.iter().map(|c| format!("{c}\n")).collect()
+}
+
+fn diff_siblings(
+    parent: &[String],
+    before: &[EntrySnapshot],
+    after: &[EntrySnapshot],
+    changes: &mut Vec<EntryChange>,
+) {
+    let mut added: Vec<&EntrySnapshot> = after
+        .iter()
+        .filter(|a| !before.iter().any(|b| b.name == a.name))
+        .collect();
+
+    for b in before {
+        if let Some(a) = after.iter().find(|a| a.name == b.name) {
+            diff_entry(parent, b, a, changes);
+            continue;
+        }
+
+        let path = join(parent, &b.name);
+
+        match added.iter().position(|a| same_except_name(a, b)) {
+            Some(i) => {
+                let a = added.remove(i);
+                changes.push(EntryChange::Renamed {
+                    from: path,
+                    to: join(parent, &a.name),
+                });
+            }
+            None => changes.push(EntryChange::Removed { path }),
+        }
+    }
+
+    for a in added {
+        changes.push(EntryChange::Added {
+            path: join(parent, &a.name),
+        });
+    }
+}
+
+fn diff_entry(
+    parent: &[String],
+    before: &EntrySnapshot,
+    after: &EntrySnapshot,
+    changes: &mut Vec<EntryChange>,
+) {
+    let path = join(parent, &after.name);
+    let fields = diff_fields(before, after);
+
+    if !fields.is_empty() {
+        changes.push(EntryChange::Modified {
+            path: path.clone(),
+            changes: fields,
+        });
+    }
+
+    diff_siblings(&path, &before.children, &after.children, changes);
+}
+
+fn diff_fields(before: &EntrySnapshot, after: &EntrySnapshot) -> Vec<FieldChange> {
+    let mut fields = Vec::new();
+
+    if before.label != after.label {
+        fields.push(FieldChange::Label(
+            before.label.clone(),
+            after.label.clone(),
+        ));
+    }
+    if before.command != after.command {
+        fields.push(FieldChange::Command(
+            before.command.clone(),
+            after.command.clone(),
+        ));
+    }
+    if before.icon != after.icon {
+        fields.push(FieldChange::Icon(before.icon.clone(), after.icon.clone()));
+    }
+    if before.position != after.position {
+        fields.push(FieldChange::Position(
+            before.position.clone(),
+            after.position.clone(),
+        ));
+    }
+    if before.separator != after.separator {
+        fields.push(FieldChange::Separator(
+            before.separator.clone(),
+            after.separator.clone(),
+        ));
+    }
+    if before.extended != after.extended {
+        fields.push(FieldChange::Extended(before.extended, after.extended));
+    }
+
+    let names = before.values.keys().chain(
+        after
+            .values
+            .keys()
+            .filter(|k| !before.values.contains_key(*k)),
+    );
+
+    for name in names {
+        let b = before.values.get(name);
+        let a = after.values.get(name);
+
+        if b != a && !covered_by_field(&fields, "", name) {
+            fields.push(FieldChange::Value {
+                name: name.clone(),
+                before: b.cloned(),
+                after: a.cloned(),
+            });
+        }
+    }
+
+    let before_keys = key_values(&before.keys);
+    let after_keys = key_values(&after.keys);
+    let names: BTreeSet<_> = before_keys.keys().chain(after_keys.keys()).collect();
+
+    for (key, name) in names {
+        let b = before_keys.get(&(key.clone(), name.clone()));
+        let a = after_keys.get(&(key.clone(), name.clone()));
+
+        if b != a && !covered_by_field(&fields, key, name) {
+            fields.push(FieldChange::KeyValue {
+                key: key.clone(),
+                name: name.clone(),
+                before: b.map(|v| (*v).clone()),
+                after: a.map(|v| (*v).clone()),
+            });
+        }
+    }
+
+    fields
+}
+
+// Checks whether a change to a stored value is already reported as a
+// change to the field it represents. Values whose field is unchanged are
+// still reported, so that e.g. a change of type is not lost.
+fn covered_by_field(fields: &[FieldChange], key: &str, name: &str) -> bool {
+    fields.iter().any(|field| {
+        let (field_key, names): (&str, &[&str]) = match field {
+            FieldChange::Label(..) => ("", &["MUIVerb"]),
+            FieldChange::Command(..) => ("command", &[""]),
+            FieldChange::Icon(..) => ("", &["Icon"]),
+            FieldChange::Position(..) => ("", &["Position"]),
+            FieldChange::Separator(..) => ("", &["SeparatorBefore", "SeparatorAfter"]),
+            FieldChange::Extended(..) => ("", &["Extended"]),
+            _ => return false,
+        };
+
+        field_key.eq_ignore_ascii_case(key) && names.iter().any(|n| n.eq_ignore_ascii_case(name))
+    })
+}
+
+// Flattens subkeys to their values by subkey path and value name.
+fn key_values(keys: &BTreeMap<String, KeySnapshot>) -> BTreeMap<(String, String), &RegValue> {
+    let mut values = BTreeMap::new();
+    collect_key_values("", keys, &mut values);
+    values
+}
+
+fn collect_key_values<'a>(
+    parent: &str,
+    keys: &'a BTreeMap<String, KeySnapshot>,
+    values: &mut BTreeMap<(String, String), &'a RegValue>,
+) {
+    for (name, key) in keys {
+        let path = if parent.is_empty() {
+            name.clone()
+        } else {
+            format!("{parent}\\{name}")
+        };
+
+        for (value_name, value) in &key.values {
+            values.insert((path.clone(), value_name.clone()), value);
+        }
+
+        collect_key_values(&path, &key.keys, values);
+    }
+}
+
+fn same_except_name(a: &EntrySnapshot, b: &EntrySnapshot) -> bool {
+    let a = EntrySnapshot {
+        name: String::new(),
+        ..a.clone()
+    };
+    let b = EntrySnapshot {
+        name: String::new(),
+        ..b.clone()
+    };
+    a == b
+}
+
+fn join(parent: &[String], name: &str) -> Vec<String> {
+    let mut path = parent.to_vec();
+    path.push(name.to_string());
+    path
+}
+
+impl fmt::Display for EntryChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntryChange::Added { path } => write!(f, "+ {}", path.join("/")),
+            EntryChange::Removed { path } => write!(f, "- {}", path.join("/")),
+            EntryChange::Renamed { from, to } => {
+                write!(f, "> {} -> {}", from.join("/"), to.join("/"))
+            }
+            EntryChange::Modified { path, changes } => {
+                write!(f, "~ {}", path.join("/"))?;
+                for change in changes {
+                    write!(f, "\n    {change}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, before, after) = match self {
+            FieldChange::Label(b, a) => ("label", show_str(b), show_str(a)),
+            FieldChange::Command(b, a) => ("command", show_str(b), show_str(a)),
+            FieldChange::Icon(b, a) => ("icon", show_str(b), show_str(a)),
+            FieldChange::Position(b, a) => ("position", show_debug(b), show_debug(a)),
+            FieldChange::Separator(b, a) => ("separator", show_debug(b), show_debug(a)),
+            FieldChange::Extended(b, a) => ("extended", b.to_string(), a.to_string()),
+            FieldChange::Value {
+                name,
+                before,
+                after,
+            } => {
+                return write!(
+                    f,
+                    "value {name:?}: {} -> {}",
+                    show_value(before),
+                    show_value(after)
+                );
+            }
+            FieldChange::KeyValue {
+                key,
+                name,
+                before,
+                after,
+            } => {
+                return write!(
+                    f,
+                    "value {name:?} in {key}: {} -> {}",
+                    show_value(before),
+                    show_value(after)
+                );
+            }
+        };
+
+        write!(f, "{name}: {before} -> {after}")
+    }
+}
+
+fn show_str(value: &Option<String>) -> String {
+    match value {
+        Some(s) => format!("{s:?}"),
+        None => "(none)".to_string(),
+    }
+}
+
+fn show_debug<T: fmt::Debug>(value: &Option<T>) -> String {
+    match value {
+        Some(v) => format!("{v:?}"),
+        None => "(none)".to_string(),
+    }
+}
+
+fn show_value(value: &Option<RegValue>) -> String {
+    match value {
+        Some(RegValue::None) => "REG_NONE".to_string(),
+        Some(RegValue::Sz(s)) => format!("{s:?}"),
+        Some(RegValue::ExpandSz(s)) => format!("{s:?} (expandable)"),
+        Some(RegValue::MultiSz(s)) => format!("{s:?}"),
+        Some(RegValue::Dword(n)) => format!("{n} (DWORD)"),
+        Some(RegValue::DwordBigEndian(n)) => format!("{n} (DWORD, big-endian)"),
+        Some(RegValue::Qword(n)) => format!("{n} (QWORD)"),
+        Some(RegValue::Binary(bytes)) => format!("{bytes:02x?}"),
+        Some(RegValue::Raw { ty, bytes }) => format!("{bytes:02x?} (type {ty})"),
+        None => "(none)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_trees() {
+        let tree = EntrySnapshot::tree("Root", vec![EntrySnapshot::tree("Child", Vec::new())]);
+        assert!(tree.diff(&tree.clone()).is_empty());
+    }
+
+    #[test]
+    fn added_and_removed_children() {
+        let before = EntrySnapshot::tree("Root", vec![EntrySnapshot::tree("Old", Vec::new())]);
+        let mut new_child = EntrySnapshot::tree("New", Vec::new());
+        new_child.command = Some("cmd".to_string());
+        let after = EntrySnapshot::tree("Root", vec![new_child]);
+
+        assert_eq!(
+            before.diff(&after),
+            [
+                EntryChange::Removed {
+                    path: vec!["Root".to_string(), "Old".to_string()]
+                },
+                EntryChange::Added {
+                    path: vec!["Root".to_string(), "New".to_string()]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn renamed_child() {
+        let mut old_child =
+            EntrySnapshot::tree("Old", vec![EntrySnapshot::tree("Grandchild", Vec::new())]);
+        old_child.icon = Some("icon".to_string());
+        let mut new_child = old_child.clone();
+        new_child.name = "New".to_string();
+
+        let before = EntrySnapshot::tree("Root", vec![old_child]);
+        let after = EntrySnapshot::tree("Root", vec![new_child]);
+
+        assert_eq!(
+            before.diff(&after),
+            [EntryChange::Renamed {
+                from: vec!["Root".to_string(), "Old".to_string()],
+                to: vec!["Root".to_string(), "New".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn modified_fields_and_values() {
+        let mut before = EntrySnapshot::tree("Root", Vec::new());
+        before.command = Some("old".to_string());
+        before
+            .values
+            .insert("Flags".to_string(), RegValue::Dword(1));
+        before
+            .values
+            .insert("Gone".to_string(), RegValue::Sz("x".to_string()));
+
+        let mut after = EntrySnapshot::tree("Root", Vec::new());
+        after.command = Some("new".to_string());
+        after.extended = true;
+        after.values.insert("Flags".to_string(), RegValue::Dword(2));
+
+        assert_eq!(
+            before.diff(&after),
+            [EntryChange::Modified {
+                path: vec!["Root".to_string()],
+                changes: vec![
+                    FieldChange::Command(Some("old".to_string()), Some("new".to_string())),
+                    FieldChange::Extended(false, true),
+                    FieldChange::Value {
+                        name: "Flags".to_string(),
+                        before: Some(RegValue::Dword(1)),
+                        after: Some(RegValue::Dword(2)),
+                    },
+                    FieldChange::Value {
+                        name: "Gone".to_string(),
+                        before: Some(RegValue::Sz("x".to_string())),
+                        after: None,
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn modified_subkey_values() {
+        let mut before = EntrySnapshot::tree("Root", Vec::new());
+        before.command = Some("cmd".to_string());
+        let mut command = KeySnapshot::default();
+        command
+            .values
+            .insert(String::new(), RegValue::Sz("cmd".to_string()));
+        command.values.insert(
+            "DelegateExecute".to_string(),
+            RegValue::Sz("{a}".to_string()),
+        );
+        before.keys.insert("command".to_string(), command);
+
+        let mut after = before.clone();
+        after.keys.get_mut("command").unwrap().values.insert(
+            "DelegateExecute".to_string(),
+            RegValue::Sz("{b}".to_string()),
+        );
+        after
+            .keys
+            .insert("DropTarget".to_string(), KeySnapshot::default());
+        after
+            .keys
+            .get_mut("DropTarget")
+            .unwrap()
+            .values
+            .insert("CLSID".to_string(), RegValue::Sz("{c}".to_string()));
+
+        let changes = before.diff(&after);
+        assert_eq!(
+            changes,
+            [EntryChange::Modified {
+                path: vec!["Root".to_string()],
+                changes: vec![
+                    FieldChange::KeyValue {
+                        key: "DropTarget".to_string(),
+                        name: "CLSID".to_string(),
+                        before: None,
+                        after: Some(RegValue::Sz("{c}".to_string())),
+                    },
+                    FieldChange::KeyValue {
+                        key: "command".to_string(),
+                        name: "DelegateExecute".to_string(),
+                        before: Some(RegValue::Sz("{a}".to_string())),
+                        after: Some(RegValue::Sz("{b}".to_string())),
+                    },
+                ],
+            }]
+        );
+        assert_eq!(
+            changes[0].to_string(),
+            concat!(
+                "~ Root\n",
+                "    value \"CLSID\" in DropTarget: (none) -> \"{c}\"\n",
+                "    value \"DelegateExecute\" in command: \"{a}\" -> \"{b}\"",
+            )
+        );
+    }
+
+    #[test]
+    fn changed_value_types() {
+        let mut before = EntrySnapshot::tree("Root", Vec::new());
+        before.extended = true;
+        before.icon = Some("a.ico".to_string());
+        before
+            .values
+            .insert("Extended".to_string(), RegValue::Sz(String::new()));
+        before
+            .values
+            .insert("Icon".to_string(), RegValue::Sz("a.ico".to_string()));
+
+        let mut after = before.clone();
+        after
+            .values
+            .insert("Extended".to_string(), RegValue::Dword(1));
+        after.icon = Some("b.ico".to_string());
+        after
+            .values
+            .insert("Icon".to_string(), RegValue::Sz("b.ico".to_string()));
+
+        assert_eq!(
+            before.diff(&after),
+            [EntryChange::Modified {
+                path: vec!["Root".to_string()],
+                changes: vec![
+                    FieldChange::Icon(Some("a.ico".to_string()), Some("b.ico".to_string())),
+                    FieldChange::Value {
+                        name: "Extended".to_string(),
+                        before: Some(RegValue::Sz(String::new())),
+                        after: Some(RegValue::Dword(1)),
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn renamed_and_modified_root() {
+        let before = EntrySnapshot::tree("Old", Vec::new());
+        let mut after = EntrySnapshot::tree("New", Vec::new());
+        after.extended = true;
+
+        assert_eq!(
+            before.diff(&after),
+            [
+                EntryChange::Renamed {
+                    from: vec!["Old".to_string()],
+                    to: vec!["New".to_string()],
+                },
+                EntryChange::Modified {
+                    path: vec!["New".to_string()],
+                    changes: vec![FieldChange::Extended(false, true)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_root_lists() {
+        let before = [
+            EntrySnapshot::tree("A", Vec::new()),
+            EntrySnapshot::tree("B", Vec::new()),
+        ];
+        let after = [
+            EntrySnapshot::tree("B", Vec::new()),
+            EntrySnapshot::tree("C", vec![EntrySnapshot::tree("D", Vec::new())]),
+        ];
+
+        assert_eq!(
+            diff_entries(&before, &after),
+            [
+                EntryChange::Removed {
+                    path: vec!["A".to_string()]
+                },
+                EntryChange::Added {
+                    path: vec!["C".to_string()]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn text_report() {
+        let mut before = EntrySnapshot::tree("Root", vec![EntrySnapshot::tree("Old", Vec::new())]);
+        before.icon = Some("a.ico".to_string());
+        let mut after = EntrySnapshot::tree(
+            "Root",
+            vec![EntrySnapshot::tree(
+                "New",
+                vec![EntrySnapshot::tree("X", Vec::new())],
+            )],
+        );
+        after.position = Some(MenuPosition::Top);
+
+        assert_eq!(
+            diff_report(&before.diff(&after)),
+            concat!(
+                "~ Root\n",
+                "    icon: \"a.ico\" -> (none)\n",
+                "    position: (none) -> Top\n",
+                "- Root/Old\n",
+                "+ Root/New\n",
+            )
+        );
+    }
+}
@@ -1,12 +1,29 @@
+use super::ConflictPolicy;
+use super::error::{Error, Result, ResultExt};
+use super::notify;
 use super::path::*;
-use std::collections::HashMap;
-use std::io::{self, ErrorKind};
-use winreg::{RegKey, enums::*};
+use super::transfer::replace_key;
+use super::walk::read_child_names;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::ops::Deref;
+use winreg::{RegKey, enums::*, types::ToRegValue};
 
-const HKCR: RegKey = RegKey::predef(HKEY_CLASSES_ROOT);
+// Gets the key entries are stored under, which is `HKEY_CLASSES_ROOT`
+// unless a test sandbox is active on the current thread.
+pub(crate) fn hkcr() -> Result<RegKey> {
+    #[cfg(feature = "testing")]
+    if let Some(root) = super::testing::sandbox_root() {
+        return root;
+    }
+
+    Ok(RegKey::predef(HKEY_CLASSES_ROOT))
+}
 
 /// Entry activation type
-#[derive(Debug, Clone)]
+///
+/// File extensions are compared ignoring case, as registry keys are.
+#[derive(Debug, Clone, Eq)]
 pub enum ActivationType {
     /// Entry activation on files (must be an extension (e.g., `.rs`) or `*` for all files)
     File(String),
@@ -16,30 +33,61 @@ pub enum ActivationType {
     Background,
 }
 
+impl PartialEq for ActivationType {
+    fn eq(&self, other: &ActivationType) -> bool {
+        match (self, other) {
+            (ActivationType::File(a), ActivationType::File(b)) => a.eq_ignore_ascii_case(b),
+            (ActivationType::Folder, ActivationType::Folder) => true,
+            (ActivationType::Background, ActivationType::Background) => true,
+            _ => false,
+        }
+    }
+}
+
 /// Entry position in the context menu
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum MenuPosition {
     Top,
     Bottom,
 }
 
 /// Context menu separator
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Separator {
     Before,
     After,
     Both,
 }
 
+/// Access rights to request when opening an entry's registry key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Reading values and listing children
+    Read,
+    /// Reading and writing values and creating subkeys
+    ReadWrite,
+}
+
 #[derive(Debug)]
 pub struct CtxEntry {
-    /// The path to the entry as a list of entry names
-    pub name_path: Vec<String>,
-    pub entry_type: ActivationType,
+    // Only changed by renaming or moving the entry, which also closes the
+    // handle.
+    pub(crate) name_path: Vec<String>,
+    pub(crate) entry_type: ActivationType,
+    // Key kept open by `open_handle`, along with its access rights.
+    pub(crate) handle: Option<(RegKey, Access)>,
 }
 
 /// Options for further customizing an entry
-#[derive(Debug, Clone)]
+///
+/// With the `serde` feature, missing fields are deserialized as their defaults.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct EntryOptions {
     /// Command to run when the entry is selected
     pub command: Option<String>,
@@ -51,6 +99,53 @@ pub struct EntryOptions {
     pub separator: Option<Separator>,
     /// Whether the entry should only appear with Shift+RClick
     pub extended: bool,
+    /// Identifier of the tool that created the entry
+    pub owner: Option<String>,
+    /// Labels by UI language, applied as with
+    /// [`CtxEntry::set_localized_labels`]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Options for renaming an entry
+#[derive(Debug, Clone, Default)]
+pub struct RenameOptions {
+    /// How to handle an existing sibling with the new name
+    pub conflict: ConflictPolicy,
+    /// Whether to also set the entry's label to the new name. Localized
+    /// labels replace it when next applied.
+    pub update_label: bool,
+}
+
+impl ActivationType {
+    /// Gets every activation type that may have entries: folders,
+    /// directory backgrounds, and each file type with a `shell` key.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry_types = ActivationType::all_registered()?;
+    /// ```
+    pub fn all_registered() -> Result<Vec<ActivationType>> {
+        let mut entry_types = vec![ActivationType::Folder, ActivationType::Background];
+        let mut file_types = Vec::new();
+
+        let root = hkcr()?;
+
+        for name in root.enum_keys() {
+            let name = name?;
+
+            if (name == "*" || name.starts_with('.'))
+                && root.open_subkey(format!("{name}\\shell")).is_ok()
+            {
+                file_types.push(name);
+            }
+        }
+
+        file_types.sort_by_key(|name| name.to_lowercase());
+        entry_types.extend(file_types.into_iter().map(ActivationType::File));
+
+        Ok(entry_types)
+    }
 }
 
 impl CtxEntry {
@@ -63,9 +158,12 @@ impl CtxEntry {
     /// let name_path = &["Root entry", "Sub entry", "Sub sub entry"];
     /// let entry = CtxEntry::get(name_path, &ActivationType::Folder)?;
     /// ``````
-    pub fn get<N: AsRef<str>>(name_path: &[N], entry_type: &ActivationType) -> Option<CtxEntry> {
+    pub fn get<N: AsRef<str>>(
+        name_path: &[N],
+        entry_type: &ActivationType,
+    ) -> Result<Option<CtxEntry>> {
         if name_path.is_empty() {
-            return None;
+            return Ok(None);
         }
 
         let mut str_path = get_base_path(entry_type);
@@ -74,20 +172,15 @@ impl CtxEntry {
             str_path.push_str(&format!("\\shell\\{entry_name}"));
         }
 
-        let key = get_key(&str_path);
-
-        if key
-            .as_ref()
-            .err()
-            .is_some_and(|e| e.kind() == ErrorKind::NotFound)
-        {
-            return None;
+        match read_key(&str_path) {
+            Ok(_) => Ok(Some(CtxEntry {
+                name_path: name_path.iter().map(|x| x.as_ref().to_string()).collect(),
+                entry_type: entry_type.clone(),
+                handle: None,
+            })),
+            Err(Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e),
         }
-
-        Some(CtxEntry {
-            name_path: name_path.iter().map(|x| x.as_ref().to_string()).collect(),
-            entry_type: entry_type.clone(),
-        })
     }
 
     /// Gets all root entries with the given entry type.
@@ -100,44 +193,59 @@ impl CtxEntry {
     pub fn get_all_of_type(entry_type: &ActivationType) -> HashMap<String, CtxEntry> {
         let mut entries = HashMap::new();
 
-        let base_path = get_base_path(entry_type);
-        let shell_path = format!("{base_path}\\shell");
-        let shell_key = match get_key(&shell_path) {
-            Ok(key) => key,
+        let names = match read_child_names(entry_type, &[]) {
+            Ok(names) => names,
             Err(_) => return entries,
         };
 
-        for entry_name in shell_key.enum_keys().map(|x| x.unwrap()) {
-            if let Some(entry) = CtxEntry::get(&[entry_name.clone()], entry_type) {
-                entries.insert(entry_name, entry);
+        // Keys that fail to read are skipped.
+        for entry_name in names.into_iter().flatten() {
+            let entry = CtxEntry {
+                name_path: vec![entry_name.clone()],
+                entry_type: entry_type.clone(),
+                handle: None,
             };
+            entries.insert(entry_name, entry);
         }
 
         entries
     }
 
-    fn create(
+    pub(crate) fn create(
         name_path: &[String],
         entry_type: &ActivationType,
         opts: &EntryOptions,
-    ) -> io::Result<CtxEntry> {
+    ) -> Result<CtxEntry> {
+        let _batch = notify::begin_batch();
         let path_str = get_full_path(entry_type, name_path);
-        let (_, disp) = HKCR.create_subkey(path_str)?;
+        let (_, disp) = hkcr()?
+            .create_subkey(&path_str)
+            .context("creating key", &path_str)?;
+        notify::changed();
 
         if disp == REG_OPENED_EXISTING_KEY {
-            return Err(io::Error::from(ErrorKind::AlreadyExists));
+            return Err(Error::already_exists().with_context("creating key", &path_str, None));
         }
 
         let mut entry = CtxEntry {
             name_path: name_path.to_vec(),
             entry_type: entry_type.clone(),
+            handle: None,
         };
 
         entry.set_command(opts.command.as_deref())?;
         entry.set_icon(opts.icon.as_deref())?;
         entry.set_position(opts.position.clone())?;
+        entry.set_separator(opts.separator.clone())?;
         entry.set_extended(opts.extended)?;
 
+        if let Some(owner) = &opts.owner {
+            entry.set_owner(Some(owner))?;
+        }
+        if !opts.labels.is_empty() {
+            entry.set_localized_labels(&opts.labels)?;
+        }
+
         Ok(entry)
     }
 
@@ -150,18 +258,8 @@ impl CtxEntry {
     /// ```no_run
     /// let mut entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// ```
-    pub fn new(name: &str, entry_type: &ActivationType) -> io::Result<CtxEntry> {
-        CtxEntry::new_with_options(
-            name,
-            entry_type,
-            &EntryOptions {
-                command: None,
-                icon: None,
-                position: None,
-                separator: None,
-                extended: false,
-            },
-        )
+    pub fn new(name: &str, entry_type: &ActivationType) -> Result<CtxEntry> {
+        CtxEntry::new_with_options(name, entry_type, &EntryOptions::default())
     }
 
     /// Creates a new top-level entry under the given `entry_type`.
@@ -174,10 +272,9 @@ impl CtxEntry {
     ///     &ActivationType::Folder,
     ///     &EntryOptions {
     ///         // This command opens the target directory in cmd.
-    ///         command: Some("cmd /s /k pushd \"%V\""),
-    ///         icon: Some("C:\\Windows\\System32\\cmd.exe"),
-    ///         position: None,
-    ///         extended: false,
+    ///         command: Some("cmd /s /k pushd \"%V\"".to_string()),
+    ///         icon: Some("C:\\Windows\\System32\\cmd.exe".to_string()),
+    ///         ..Default::default()
     ///     }
     /// )?;
     /// ```
@@ -185,7 +282,7 @@ impl CtxEntry {
         name: &str,
         entry_type: &ActivationType,
         opts: &EntryOptions,
-    ) -> io::Result<CtxEntry> {
+    ) -> Result<CtxEntry> {
         let name_path = [name.to_string()];
         CtxEntry::create(&name_path, entry_type, opts)
     }
@@ -198,8 +295,12 @@ impl CtxEntry {
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// entry.delete()?;
     /// ```
-    pub fn delete(self) -> io::Result<()> {
-        HKCR.delete_subkey_all(self.path())
+    pub fn delete(self) -> Result<()> {
+        let path = self.path();
+        hkcr()?
+            .delete_subkey_all(&path)
+            .context("deleting key", &path)
+            .inspect(|_| notify::changed())
     }
 
     /// Gets the entry's current name.
@@ -210,12 +311,12 @@ impl CtxEntry {
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// let name = entry.name()?;
     /// ```
-    pub fn name(&self) -> io::Result<String> {
+    pub fn name(&self) -> Result<String> {
         let _ = self.key()?;
         Ok(self.name_path.last().unwrap().to_owned())
     }
 
-    /// Renames the entry.
+    /// Renames the entry, failing if a sibling with the new name exists.
     ///
     /// # Examples
     ///
@@ -223,25 +324,97 @@ impl CtxEntry {
     /// let mut entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// entry.rename("Renamed entry")?;
     /// ```
-    pub fn rename(&mut self, new_name: &str) -> io::Result<()> {
-        if new_name.is_empty() {
-            return Err(io::Error::new(
-                ErrorKind::InvalidInput,
-                "Name cannot be empty",
-            ));
-        }
+    pub fn rename(&mut self, new_name: &str) -> Result<()> {
+        self.rename_with_options(new_name, &RenameOptions::default())
+    }
+
+    /// Renames the entry. The entry is only updated if the rename succeeds,
+    /// and an overwritten sibling is kept if it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
+    /// entry.rename_with_options(
+    ///     "Renamed entry",
+    ///     &RenameOptions {
+    ///         conflict: ConflictPolicy::Overwrite,
+    ///         update_label: true,
+    ///     }
+    /// )?;
+    /// ```
+    pub fn rename_with_options(&mut self, new_name: &str, opts: &RenameOptions) -> Result<()> {
+        validate_name(new_name)?;
 
+        let _batch = notify::begin_batch();
         let old_name = self.name()?;
+        let shell_path = self.shell_path();
+        let shell_key = get_key(&shell_path)?;
 
-        let parent_name_path = &self.name_path[..self.name_path.len() - 1];
-        let parent_path_str = get_full_path(&self.entry_type, parent_name_path);
-        let parent_key = HKCR.open_subkey(parent_path_str)?;
-        let res = parent_key.rename_subkey(old_name, new_name);
+        let conflict =
+            !old_name.eq_ignore_ascii_case(new_name) && shell_key.open_subkey(new_name).is_ok();
+
+        let result = match opts.conflict {
+            ConflictPolicy::Fail if conflict => Err(Error::already_exists()),
+            ConflictPolicy::Merge if conflict => shell_key
+                .create_subkey(new_name)
+                .and_then(|(target_key, _)| shell_key.copy_tree(&old_name, &target_key))
+                .and_then(|_| shell_key.delete_subkey_all(&old_name))
+                .map_err(Error::from),
+            ConflictPolicy::Overwrite if conflict => {
+                replace_key(&shell_key, &shell_path, &old_name, new_name)
+            }
+            _ if old_name != new_name => shell_key
+                .rename_subkey(&old_name, new_name)
+                .map_err(Error::from),
+            _ => Ok(()),
+        };
+
+        result.context("renaming key", &format!("{shell_path}\\{old_name}"))?;
+        notify::changed();
+        self.handle = None;
 
         let path_len = self.name_path.len();
         self.name_path[path_len - 1] = new_name.to_string();
 
-        res
+        if opts.update_label {
+            self.set_label(Some(new_name))?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the entry's visible label. This is the entry's name unless
+    /// overridden with [`CtxEntry::set_label`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
+    /// let label = entry.label()?;
+    /// ```
+    pub fn label(&self) -> Result<String> {
+        let key = self.key()?;
+        match key.get_value::<String, _>("MUIVerb") {
+            Ok(label) => Ok(label),
+            Err(_) => self.name(),
+        }
+    }
+
+    /// Sets the entry's visible label independently of its name.
+    /// Passing `None` makes the entry's name visible again.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::new("01_Terminal", ActivationType::Background)?;
+    /// entry.set_label(Some("Terminal"))?;
+    /// ```
+    pub fn set_label(&mut self, label: Option<&str>) -> Result<()> {
+        match label {
+            Some(label) => self.write_value("MUIVerb", &label),
+            None => self.safe_delete_value("MUIVerb"),
+        }
     }
 
     /// Gets the entry's command, if any.
@@ -252,10 +425,13 @@ impl CtxEntry {
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// let command = entry.command()?;
     /// ```
-    pub fn command(&self) -> io::Result<Option<String>> {
-        let path = format!(r"{}\command", self.path());
-        let key = get_key(&path)?;
-        Ok(key.get_value::<String, _>("").ok())
+    pub fn command(&self) -> Result<Option<String>> {
+        let key = self.key()?;
+        match key.open_subkey("command") {
+            Ok(command_key) => Ok(command_key.get_value::<String, _>("").ok()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).context("opening key", &format!("{}\\command", self.path())),
+        }
     }
 
     /// Sets the entry's command.
@@ -267,19 +443,27 @@ impl CtxEntry {
     /// // This command opens the target directory in Powershell.
     /// entry.set_command(Some("powershell.exe -noexit -command Set-Location -literalPath '%V'"))?;
     /// ```
-    pub fn set_command(&mut self, command: Option<&str>) -> io::Result<()> {
-        let key = self.key()?;
-        match command {
+    pub fn set_command(&mut self, command: Option<&str>) -> Result<()> {
+        let key = self.write_key()?;
+        let command_path = format!("{}\\command", self.path());
+
+        let result = match command {
             Some(c) => {
-                let (command_key, _) = key.create_subkey("command")?;
-                command_key.set_value("", &c)
+                let (command_key, _) = key
+                    .create_subkey("command")
+                    .context("creating key", &command_path)?;
+                command_key
+                    .set_value("", &c)
+                    .value_context("writing value", &command_path, "")
             }
             None => match key.delete_subkey("command") {
-                Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
-                Err(e) => Err(e),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(e).context("deleting key", &command_path),
                 Ok(_) => Ok(()),
             },
-        }
+        };
+
+        result.inspect(|_| notify::changed())
     }
 
     /// Gets the entry's icon, if any.
@@ -290,7 +474,7 @@ impl CtxEntry {
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// let icon = entry.icon()?;
     /// ```
-    pub fn icon(&self) -> io::Result<Option<String>> {
+    pub fn icon(&self) -> Result<Option<String>> {
         let key = self.key()?;
         Ok(key.get_value::<String, _>("Icon").ok())
     }
@@ -303,15 +487,15 @@ impl CtxEntry {
     /// let mut entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// entry.set_icon(Some("C:\\Windows\\System32\\control.exe"))?;
     /// ```
-    pub fn set_icon(&mut self, icon: Option<&str>) -> io::Result<()> {
-        let key = self.key()?;
+    pub fn set_icon(&mut self, icon: Option<&str>) -> Result<()> {
         match icon {
-            Some(icon) => key.set_value("Icon", &icon),
+            Some(icon) => self.write_value("Icon", &icon),
             None => self.safe_delete_value("Icon"),
         }
     }
 
-    /// Gets the entry's position, if any.
+    /// Gets the entry's position, if any. The `Position` value is parsed
+    /// ignoring case.
     ///
     /// # Examples
     ///
@@ -319,15 +503,10 @@ impl CtxEntry {
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// let position = entry.position()?;
     /// ```
-    pub fn position(&self) -> io::Result<Option<MenuPosition>> {
+    pub fn position(&self) -> Result<Option<MenuPosition>> {
         let key = self.key()?;
-        let val = match key.get_value::<String, _>("Position") {
-            Ok(v) if v == "Top" => Some(MenuPosition::Top),
-            Ok(v) if v == "Bottom" => Some(MenuPosition::Bottom),
-            _ => None,
-        };
-
-        Ok(val)
+        let val = key.get_value::<String, _>("Position").ok();
+        Ok(val.as_deref().and_then(parse_position))
     }
 
     /// Sets the entry's menu position. By default, new root entries are
@@ -339,21 +518,15 @@ impl CtxEntry {
     /// let mut entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// entry.set_position(Some(MenuPosition::Bottom))?;
     /// ```
-    pub fn set_position(&mut self, position: Option<MenuPosition>) -> io::Result<()> {
-        if position.is_none() {
-            return self.safe_delete_value("Position");
+    pub fn set_position(&mut self, position: Option<MenuPosition>) -> Result<()> {
+        match position {
+            Some(position) => self.write_value("Position", &position_str(&position)),
+            None => self.safe_delete_value("Position"),
         }
-
-        let position_str = match position {
-            Some(MenuPosition::Top) => "Top",
-            Some(MenuPosition::Bottom) => "Bottom",
-            None => "",
-        };
-
-        self.key()?.set_value("Position", &position_str)
     }
 
-    /// Gets whether the entry appears with Shift+RClick.
+    /// Gets whether the entry appears with Shift+RClick. The `Extended`
+    /// value is recognized with any value type.
     ///
     /// # Examples
     ///
@@ -361,9 +534,9 @@ impl CtxEntry {
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// let is_extended = entry.extended()?;
     /// ```
-    pub fn extended(&self) -> io::Result<bool> {
+    pub fn extended(&self) -> Result<bool> {
         let key = self.key()?;
-        Ok(key.get_value::<String, _>("Extended").ok().is_some())
+        Ok(key.get_raw_value("Extended").is_ok())
     }
 
     /// Sets whether the entry should only appear with Shift+RClick.
@@ -374,33 +547,59 @@ impl CtxEntry {
     /// let mut entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// entry.set_extended(true)?;
     /// ```
-    pub fn set_extended(&mut self, extended: bool) -> io::Result<()> {
+    pub fn set_extended(&mut self, extended: bool) -> Result<()> {
         if extended {
-            self.key()?.set_value("Extended", &"")
+            self.write_value("Extended", &"")
         } else {
             self.safe_delete_value("Extended")
         }
     }
 
-    /// Gets the entry's separator(s), if any.
+    /// Gets whether the entry is hidden from the context menu with the
+    /// `LegacyDisable` value, which is recognized with any value type.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
-    /// let separator = entry.separator()?;
+    /// let is_disabled = entry.disabled()?;
     /// ```
-    pub fn separator(&self) -> io::Result<Option<Separator>> {
+    pub fn disabled(&self) -> Result<bool> {
         let key = self.key()?;
-        let sep_before = key.get_value::<String, _>("SeparatorBefore");
-        let sep_after = key.get_value::<String, _>("SeparatorAfter");
+        Ok(key.get_raw_value("LegacyDisable").is_ok())
+    }
+
+    /// Sets whether the entry is hidden from the context menu, keeping it
+    /// in the registry so it can be shown again.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
+    /// entry.set_disabled(true)?;
+    /// ```
+    pub fn set_disabled(&mut self, disabled: bool) -> Result<()> {
+        if disabled {
+            self.write_value("LegacyDisable", &"")
+        } else {
+            self.safe_delete_value("LegacyDisable")
+        }
+    }
 
-        Ok(match (sep_before, sep_after) {
-            (Ok(_), Ok(_)) => Some(Separator::Both),
-            (Ok(_), Err(_)) => Some(Separator::Before),
-            (Err(_), Ok(_)) => Some(Separator::After),
-            _ => None,
-        })
+    /// Gets the entry's separator(s), if any. Separator values are
+    /// recognized with any value type.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
+    /// let separator = entry.separator()?;
+    /// ```
+    pub fn separator(&self) -> Result<Option<Separator>> {
+        let key = self.key()?;
+        let sep_before = key.get_raw_value("SeparatorBefore").is_ok();
+        let sep_after = key.get_raw_value("SeparatorAfter").is_ok();
+        Ok(parse_separator(sep_before, sep_after))
     }
 
     /// Sets the entry's separator(s).
@@ -411,22 +610,21 @@ impl CtxEntry {
     /// let mut entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// entry.set_separator(Some(Separator::After))?;
     /// ```
-    pub fn set_separator(&mut self, separator: Option<Separator>) -> io::Result<()> {
-        let key = self.key()?;
+    pub fn set_separator(&mut self, separator: Option<Separator>) -> Result<()> {
         match separator {
             Some(Separator::Before) => {
-                key.set_value("SeparatorBefore", &"")?;
+                self.write_value("SeparatorBefore", &"")?;
                 self.safe_delete_value("SeparatorAfter")?;
                 Ok(())
             }
             Some(Separator::After) => {
-                key.set_value("SeparatorAfter", &"")?;
+                self.write_value("SeparatorAfter", &"")?;
                 self.safe_delete_value("SeparatorBefore")?;
                 Ok(())
             }
             Some(Separator::Both) => {
-                key.set_value("SeparatorBefore", &"")?;
-                key.set_value("SeparatorAfter", &"")?;
+                self.write_value("SeparatorBefore", &"")?;
+                self.write_value("SeparatorAfter", &"")?;
                 Ok(())
             }
             None => {
@@ -444,12 +642,12 @@ impl CtxEntry {
     /// ```no_run
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// let child = entry.new_child("Basic child entry")?;
-    /// let parent = child.parent()?;
+    /// let parent = child.parent()?.unwrap();
     /// assert_eq!(entry.name().unwrap(), parent.name().unwrap());
     /// ```
-    pub fn parent(&self) -> Option<CtxEntry> {
+    pub fn parent(&self) -> Result<Option<CtxEntry>> {
         if self.name_path.len() <= 1 {
-            return None;
+            return Ok(None);
         }
 
         let parent_path = &self.name_path[..self.name_path.len() - 1];
@@ -466,22 +664,24 @@ impl CtxEntry {
     /// let retrieved_child = entry.child("Basic child entry")?;
     /// assert_eq!(created_child.name().unwrap(), retrieved_child.name().unwrap());
     /// ```
-    pub fn child(&self, name: &str) -> io::Result<Option<CtxEntry>> {
+    pub fn child(&self, name: &str) -> Result<Option<CtxEntry>> {
         let mut name_path = self.name_path.clone();
         name_path.push(name.to_string());
         let path_str = get_full_path(&self.entry_type, &name_path);
 
-        match get_key(&path_str) {
+        match read_key(&path_str) {
             Ok(_) => Ok(Some(CtxEntry {
                 name_path,
                 entry_type: self.entry_type.clone(),
+                handle: None,
             })),
-            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(Error::NotFound { .. }) => Ok(None),
             Err(e) => Err(e),
         }
     }
 
-    /// Gets the entry's children, if any.
+    /// Gets the entry's children, if any, in the order they appear in
+    /// the context menu. Children whose names fail to read are skipped.
     ///
     /// # Examples
     ///
@@ -491,25 +691,193 @@ impl CtxEntry {
     /// let child_2 = entry.new_child("Child 2")?;
     /// let children = entry.children()?;
     /// ```
-    pub fn children(&self) -> io::Result<Vec<CtxEntry>> {
-        let path = format!("{}\\shell", self.path());
-        let key = get_key(&path);
+    pub fn children(&self) -> Result<Vec<CtxEntry>> {
+        let mut children = Vec::new();
+
+        for name in read_child_names(&self.entry_type, &self.name_path)?
+            .into_iter()
+            .flatten()
+        {
+            let mut name_path = self.name_path.clone();
+            name_path.push(name);
 
-        if key.is_err() {
-            return Ok(Vec::new());
+            children.push(CtxEntry {
+                name_path,
+                entry_type: self.entry_type.clone(),
+                handle: None,
+            });
         }
 
-        let mut children = Vec::new();
+        Ok(children)
+    }
+
+    // Checks whether the entry has children, including any whose names
+    // fail to read.
+    pub(crate) fn has_children(&self) -> Result<bool> {
+        Ok(!read_child_names(&self.entry_type, &self.name_path)?.is_empty())
+    }
+
+    /// Reorders the entry's children to match the given labels. Children
+    /// not listed keep their relative order after the listed ones. Fails
+    /// with `InvalidInput` if a listed label is used by more than one child.
+    ///
+    /// Children are renamed with a sortable prefix (e.g., `01_Terminal`) and
+    /// their labels are preserved with `MUIVerb`, so existing handles to the
+    /// children become stale. Children already named for their position are
+    /// left as is. If renaming fails, children renamed so far are renamed
+    /// back. The returned handles are in the new order.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Open directory in", &ActivationType::Background)?;
+    /// entry.new_child("Terminal")?;
+    /// entry.new_child("Powershell")?;
+    /// let children = entry.reorder_children(&["Terminal", "Powershell"])?;
+    /// ```
+    pub fn reorder_children<N: AsRef<str>>(&self, labels: &[N]) -> Result<Vec<CtxEntry>> {
+        let _batch = notify::begin_batch();
+        let mut remaining = Vec::new();
+
+        for child in self.children()? {
+            let label = child.label()?;
+            remaining.push((child, label));
+        }
+
+        let mut ordered = Vec::new();
+
+        for label in labels.iter().map(|x| x.as_ref()) {
+            if remaining.iter().filter(|(_, l)| l == label).count() > 1 {
+                return Err(Error::invalid_value(format!(
+                    "more than one child labeled {label:?}"
+                )));
+            }
+
+            let pos = remaining
+                .iter()
+                .position(|(_, l)| l == label)
+                .ok_or_else(|| Error::invalid_value(format!("no child labeled {label:?}")))?;
+            ordered.push(remaining.remove(pos));
+        }
+
+        ordered.append(&mut remaining);
 
-        for name in key.unwrap().enum_keys().map(|x| x.unwrap()) {
-            if let Some(child) = self.child(&name).unwrap() {
-                children.push(child)
+        let shell_path = format!("{}\\shell", self.path());
+        let shell_key = get_key(&shell_path)?;
+        let count = ordered.len();
+        let width = count.saturating_sub(1).to_string().len().max(2);
+
+        // Temporary names must not clash with any child, including children
+        // whose names fail to read.
+        let taken: HashSet<String> = read_child_names(&self.entry_type, &self.name_path)?
+            .into_iter()
+            .flatten()
+            .map(|name| name.to_lowercase())
+            .collect();
+
+        // Children to rename, with their old, temporary and new names.
+        let mut moves = Vec::new();
+
+        for (i, (child, _)) in ordered.iter().enumerate() {
+            let name = child.name()?;
+            let has_label = child.get_value("MUIVerb")?.is_some();
+            let base_name = match has_label {
+                true => strip_order_prefix(&name, count),
+                false => &name,
+            };
+            let new_name = format!("{i:0width$}_{base_name}");
+
+            if new_name != name {
+                let temp_name = free_name(&format!("~reorder{i}"), &taken);
+                moves.push((i, name, temp_name, new_name, has_label));
+            }
+        }
+
+        // Renames done so far, undone in reverse if a later one fails.
+        let mut renamed: Vec<(String, String)> = Vec::new();
+        let mut labeled = Vec::new();
+
+        let mut rename = |from: &str, to: &str| -> Result<()> {
+            shell_key
+                .rename_subkey(from, to)
+                .context("renaming key", &format!("{shell_path}\\{from}"))?;
+            notify::changed();
+            renamed.push((from.to_string(), to.to_string()));
+            Ok(())
+        };
+
+        let result = (|| {
+            // Rename in two passes so new names never clash with old ones.
+            for (i, name, temp_name, _, has_label) in &moves {
+                let (child, label) = &ordered[*i];
+
+                if !has_label {
+                    child.write_value("MUIVerb", label)?;
+                    labeled.push(*i);
+                }
+
+                rename(name, temp_name)?;
+            }
+
+            for (_, _, temp_name, new_name, _) in &moves {
+                rename(temp_name, new_name)?;
+            }
+
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            for (from, to) in renamed.iter().rev() {
+                let _ = shell_key.rename_subkey(to, from);
+            }
+            for i in labeled {
+                let _ = ordered[i].0.safe_delete_value("MUIVerb");
             }
+            notify::changed();
+            return Err(e);
+        }
+
+        let mut children: Vec<CtxEntry> = ordered.into_iter().map(|(child, _)| child).collect();
+
+        for (i, _, _, new_name, _) in moves {
+            *children[i].name_path.last_mut().unwrap() = new_name;
         }
 
         Ok(children)
     }
 
+    /// Moves the child with the given label directly before another child,
+    /// as with [`CtxEntry::reorder_children`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Open directory in", &ActivationType::Background)?;
+    /// entry.new_child("Terminal")?;
+    /// entry.new_child("Powershell")?;
+    /// let children = entry.move_child_before("Terminal", "Powershell")?;
+    /// ```
+    pub fn move_child_before(&self, label: &str, before: &str) -> Result<Vec<CtxEntry>> {
+        let mut labels = Vec::new();
+
+        for child in self.children()? {
+            labels.push(child.label()?);
+        }
+
+        let from = labels
+            .iter()
+            .position(|l| l == label)
+            .ok_or_else(|| Error::invalid_value(format!("no child labeled {label:?}")))?;
+        let moved = labels.remove(from);
+        let to = labels
+            .iter()
+            .position(|l| l == before)
+            .ok_or_else(|| Error::invalid_value(format!("no child labeled {before:?}")))?;
+        labels.insert(to, moved);
+
+        self.reorder_children(&labels)
+    }
+
     /// Creates a new child entry under the entry. The resulting entry
     /// will appear in the context menu but will do nothing until modified.
     ///
@@ -519,17 +887,8 @@ impl CtxEntry {
     /// let entry = CtxEntry::new("Basic entry", ActivationType::Background)?;
     /// let child = entry.new_child("Basic child entry")?;
     /// ```
-    pub fn new_child(&self, name: &str) -> io::Result<CtxEntry> {
-        self.new_child_with_options(
-            name,
-            &EntryOptions {
-                command: None,
-                icon: None,
-                position: None,
-                separator: None,
-                extended: false,
-            },
-        )
+    pub fn new_child(&self, name: &str) -> Result<CtxEntry> {
+        self.new_child_with_options(name, &EntryOptions::default())
     }
 
     /// Creates a new child entry under the entry.
@@ -542,16 +901,15 @@ impl CtxEntry {
     ///     "Basic child entry",
     ///     &EntryOptions {
     ///         // This command opens the target directory in cmd.
-    ///         command: Some("cmd /s /k pushd \"%V\""),
-    ///         icon: Some("C:\\Windows\\System32\\cmd.exe"),
-    ///         position: None,
-    ///         extended: false,
+    ///         command: Some("cmd /s /k pushd \"%V\"".to_string()),
+    ///         icon: Some("C:\\Windows\\System32\\cmd.exe".to_string()),
+    ///         ..Default::default()
     ///     }
     /// )?;
     /// ```
-    pub fn new_child_with_options(&self, name: &str, opts: &EntryOptions) -> io::Result<CtxEntry> {
-        let key = self.key()?;
-        key.set_value("Subcommands", &"")?;
+    pub fn new_child_with_options(&self, name: &str, opts: &EntryOptions) -> Result<CtxEntry> {
+        let _batch = notify::begin_batch();
+        self.write_value("Subcommands", &"")?;
 
         let mut path = self.name_path.clone();
         path.push(name.to_string());
@@ -559,6 +917,23 @@ impl CtxEntry {
         CtxEntry::create(path.as_slice(), &self.entry_type, opts)
     }
 
+    /// Gets the path to the entry as a list of entry names.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::get(&["Open directory in", "Terminal"], &ActivationType::Background)?.unwrap();
+    /// assert_eq!(entry.name_path(), ["Open directory in", "Terminal"]);
+    /// ```
+    pub fn name_path(&self) -> &[String] {
+        &self.name_path
+    }
+
+    /// Gets the entry's activation type.
+    pub fn entry_type(&self) -> &ActivationType {
+        &self.entry_type
+    }
+
     /// Gets the full path to the entry's registry key.
     ///
     /// # Examples
@@ -571,30 +946,218 @@ impl CtxEntry {
         get_full_path(&self.entry_type, &self.name_path)
     }
 
-    // Shortcut to get the entry's registry key.
+    // Gets the path to the key containing the entry and its siblings.
+    pub(crate) fn shell_path(&self) -> String {
+        let parent_name_path = &self.name_path[..self.name_path.len() - 1];
+        let parent_path = get_full_path(&self.entry_type, parent_name_path);
+
+        if parent_name_path.is_empty() {
+            parent_path
+        } else {
+            format!("{parent_path}\\shell")
+        }
+    }
+
+    /// Opens the entry's key with the given access and keeps it open, so
+    /// later calls reuse it instead of opening the key each time. Calls
+    /// needing more access than the handle has still open the key
+    /// themselves. Renaming or moving the entry closes the handle.
+    ///
+    /// An open handle keeps working if the key is deleted by someone else,
+    /// so calls fail instead of reporting the entry as missing.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::get(&["Open directory in"], &ActivationType::Background)?.unwrap();
+    /// entry.open_handle(Access::Read)?;
+    /// let label = entry.label()?;
+    /// let command = entry.command()?;
+    /// ```
+    pub fn open_handle(&mut self, access: Access) -> Result<()> {
+        self.handle = None;
+        let key = open_key(&self.path(), access)?;
+        self.handle = Some((key, access));
+        Ok(())
+    }
+
+    /// Closes a handle opened with [`CtxEntry::open_handle`], if any.
+    pub fn close_handle(&mut self) {
+        self.handle = None;
+    }
+
+    // Shortcut to get the entry's registry key for reading.
     // Should be checked before every operation.
-    fn key(&self) -> io::Result<RegKey> {
-        get_key(&self.path())
+    pub(crate) fn key(&self) -> Result<KeyRef<'_>> {
+        self.key_with(Access::Read)
+    }
+
+    // Gets the entry's registry key for writing values and creating subkeys.
+    pub(crate) fn write_key(&self) -> Result<KeyRef<'_>> {
+        self.key_with(Access::ReadWrite)
+    }
+
+    fn key_with(&self, access: Access) -> Result<KeyRef<'_>> {
+        match &self.handle {
+            Some((key, held)) if *held == Access::ReadWrite || access == Access::Read => {
+                Ok(KeyRef::Cached(key))
+            }
+            _ => open_key(&self.path(), access).map(KeyRef::Opened),
+        }
+    }
+
+    // Sets a value on the entry's key.
+    pub(crate) fn write_value<T: ToRegValue>(&self, name: &str, value: &T) -> Result<()> {
+        let path = self.path();
+        self.write_key()?
+            .set_value(name, value)
+            .value_context("writing value", &path, name)
+            .inspect(|_| notify::changed())
     }
 
     // Delete value without erroring if nonexistent.
-    fn safe_delete_value(&self, value: &str) -> io::Result<()> {
-        let key = self.key()?;
-        match key.delete_value(value) {
-            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
-            Err(e) => Err(e),
-            Ok(_) => Ok(()),
+    pub(crate) fn safe_delete_value(&self, value: &str) -> Result<()> {
+        let path = self.path();
+        match self.write_key()?.delete_value(value) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).value_context("deleting value", &path, value),
+            Ok(_) => {
+                notify::changed();
+                Ok(())
+            }
+        }
+    }
+}
+
+// Registry key that is either kept open by an entry or opened for one call.
+pub(crate) enum KeyRef<'a> {
+    Cached(&'a RegKey),
+    Opened(RegKey),
+}
+
+impl Deref for KeyRef<'_> {
+    type Target = RegKey;
+
+    fn deref(&self) -> &RegKey {
+        match self {
+            KeyRef::Cached(key) => key,
+            KeyRef::Opened(key) => key,
         }
     }
 }
 
-fn get_key(path: &str) -> io::Result<RegKey> {
-    match HKCR.open_subkey_with_flags(path, KEY_ALL_ACCESS) {
-        Err(e) if e.kind() == ErrorKind::NotFound => Err(io::Error::new(
-            ErrorKind::NotFound,
-            "Registry key does not exist",
-        )),
-        Err(e) => Err(e),
-        Ok(key) => Ok(key),
+// Opens a key for renaming, copying or deleting its subkeys.
+pub(crate) fn get_key(path: &str) -> Result<RegKey> {
+    hkcr()?
+        .open_subkey_with_flags(path, KEY_ALL_ACCESS)
+        .context("opening key", path)
+}
+
+// Opens a key only to read it or check that it exists.
+pub(crate) fn read_key(path: &str) -> Result<RegKey> {
+    open_key(path, Access::Read)
+}
+
+pub(crate) fn open_key(path: &str, access: Access) -> Result<RegKey> {
+    let flags = match access {
+        Access::Read => KEY_READ,
+        Access::ReadWrite => KEY_READ | KEY_WRITE,
+    };
+
+    hkcr()?
+        .open_subkey_with_flags(path, flags)
+        .context("opening key", path)
+}
+
+pub(crate) fn parse_position(value: &str) -> Option<MenuPosition> {
+    if value.eq_ignore_ascii_case("Top") {
+        Some(MenuPosition::Top)
+    } else if value.eq_ignore_ascii_case("Bottom") {
+        Some(MenuPosition::Bottom)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn position_str(position: &MenuPosition) -> &'static str {
+    match position {
+        MenuPosition::Top => "Top",
+        MenuPosition::Bottom => "Bottom",
+    }
+}
+
+pub(crate) fn parse_separator(before: bool, after: bool) -> Option<Separator> {
+    match (before, after) {
+        (true, true) => Some(Separator::Both),
+        (true, false) => Some(Separator::Before),
+        (false, true) => Some(Separator::After),
+        (false, false) => None,
+    }
+}
+
+// Gets the first of `base`, `base-1`, `base-2`, etc. that is not in the
+// given set of lowercase names.
+fn free_name(base: &str, taken: &HashSet<String>) -> String {
+    let mut name = base.to_string();
+    let mut n = 1;
+
+    while taken.contains(&name.to_lowercase()) {
+        name = format!("{base}-{n}");
+        n += 1;
     }
+
+    name
+}
+
+// Strips a sortable prefix (e.g., `01_`) assigned by `reorder_children`
+// from the name of a labeled child. Only prefixes it could have assigned
+// among the given number of children are stripped, so names such as
+// `2023_report` are kept.
+fn strip_order_prefix(name: &str, count: usize) -> &str {
+    let max_width = count.saturating_sub(1).to_string().len().max(2);
+
+    match name.split_once('_') {
+        Some((prefix, base))
+            if (2..=max_width).contains(&prefix.len())
+                && prefix.bytes().all(|b| b.is_ascii_digit())
+                && prefix.parse::<usize>().is_ok_and(|i| i < count) =>
+        {
+            base
+        }
+        _ => name,
+    }
+}
+
+// Canonical verbs with special meaning to Explorer.
+const RESERVED_NAMES: [&str; 11] = [
+    "edit",
+    "explore",
+    "find",
+    "open",
+    "openas",
+    "opennew",
+    "print",
+    "printto",
+    "properties",
+    "runas",
+    "runasuser",
+];
+
+// Registry key names are limited to 255 characters.
+pub(crate) const MAX_NAME_LEN: usize = 255;
+
+pub(crate) fn validate_name(name: &str) -> Result<()> {
+    let reason = if name.is_empty() {
+        "name cannot be empty"
+    } else if name.contains('\\') {
+        "name cannot contain backslashes"
+    } else if name.chars().count() > MAX_NAME_LEN {
+        "name cannot be longer than 255 characters"
+    } else if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(name)) {
+        "name is reserved for a canonical verb"
+    } else {
+        return Ok(());
+    };
+
+    Err(Error::invalid_name(name, reason))
 }
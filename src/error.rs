@@ -0,0 +1,279 @@
+use std::{error, fmt, io};
+
+/// Result type returned by this crate
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Where in the registry an error happened
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Operation that failed (e.g., `"opening key"`)
+    pub operation: Option<&'static str>,
+    /// Path of the registry key involved
+    pub path: Option<String>,
+    /// Name of the registry value involved
+    pub value: Option<String>,
+}
+
+/// Error returned by this crate
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A registry key or value does not exist
+    NotFound { context: ErrorContext },
+    /// An entry with the same name already exists
+    AlreadyExists { context: ErrorContext },
+    /// Access to a registry key was denied, or an entry is protected
+    PermissionDenied { context: ErrorContext },
+    /// An entry name cannot be used
+    InvalidName {
+        name: String,
+        reason: String,
+        context: ErrorContext,
+    },
+    /// An argument or registry value is malformed
+    InvalidValue {
+        reason: String,
+        context: ErrorContext,
+    },
+    /// Any other error from the registry
+    Backend {
+        source: io::Error,
+        context: ErrorContext,
+    },
+}
+
+impl Error {
+    /// Gets where the error happened.
+    pub fn context(&self) -> &ErrorContext {
+        match self {
+            Error::NotFound { context }
+            | Error::AlreadyExists { context }
+            | Error::PermissionDenied { context }
+            | Error::InvalidName { context, .. }
+            | Error::InvalidValue { context, .. }
+            | Error::Backend { context, .. } => context,
+        }
+    }
+
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        match self {
+            Error::NotFound { context }
+            | Error::AlreadyExists { context }
+            | Error::PermissionDenied { context }
+            | Error::InvalidName { context, .. }
+            | Error::InvalidValue { context, .. }
+            | Error::Backend { context, .. } => context,
+        }
+    }
+
+    /// Gets the closest matching I/O error kind.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// match entry.rename("Renamed entry") {
+    ///     Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+    ///     result => result?,
+    /// }
+    /// ```
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Error::NotFound { .. } => io::ErrorKind::NotFound,
+            Error::AlreadyExists { .. } => io::ErrorKind::AlreadyExists,
+            Error::PermissionDenied { .. } => io::ErrorKind::PermissionDenied,
+            Error::InvalidName { .. } | Error::InvalidValue { .. } => io::ErrorKind::InvalidInput,
+            Error::Backend { source, .. } => source.kind(),
+        }
+    }
+
+    pub(crate) fn not_found() -> Error {
+        Error::NotFound {
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub(crate) fn already_exists() -> Error {
+        Error::AlreadyExists {
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub(crate) fn permission_denied() -> Error {
+        Error::PermissionDenied {
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub(crate) fn invalid_name(name: &str, reason: &str) -> Error {
+        Error::InvalidName {
+            name: name.to_string(),
+            reason: reason.to_string(),
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub(crate) fn invalid_value(reason: impl Into<String>) -> Error {
+        Error::InvalidValue {
+            reason: reason.into(),
+            context: ErrorContext::default(),
+        }
+    }
+
+    // Fills in any context not already set closer to the failure.
+    pub(crate) fn with_context(
+        mut self,
+        operation: &'static str,
+        path: &str,
+        value: Option<&str>,
+    ) -> Error {
+        let context = self.context_mut();
+        context.operation.get_or_insert(operation);
+        context.path.get_or_insert_with(|| path.to_string());
+
+        if let Some(value) = value {
+            context.value.get_or_insert_with(|| value.to_string());
+        }
+
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NotFound { .. } => write!(f, "Registry key or value does not exist")?,
+            Error::AlreadyExists { .. } => write!(f, "Entry already exists")?,
+            Error::PermissionDenied { .. } => write!(f, "Permission denied")?,
+            Error::InvalidName { name, reason, .. } => {
+                write!(f, "Invalid entry name {name:?}: {reason}")?
+            }
+            Error::InvalidValue { reason, .. } => write!(f, "Invalid value: {reason}")?,
+            Error::Backend { source, .. } => write!(f, "Registry error: {source}")?,
+        }
+
+        let context = self.context();
+
+        if let Some(operation) = context.operation {
+            write!(f, " while {operation}")?;
+        }
+        if let Some(path) = &context.path {
+            write!(f, " at {path}")?;
+        }
+        if let Some(value) = &context.value {
+            write!(f, " (value {value:?})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Backend { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        if e.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            return *e.into_inner().unwrap().downcast::<Error>().unwrap();
+        }
+
+        match e.kind() {
+            io::ErrorKind::NotFound => Error::not_found(),
+            io::ErrorKind::AlreadyExists => Error::already_exists(),
+            io::ErrorKind::PermissionDenied => Error::permission_denied(),
+            io::ErrorKind::InvalidInput => Error::invalid_value(e.to_string()),
+            _ => Error::Backend {
+                source: e,
+                context: ErrorContext::default(),
+            },
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> io::Error {
+        io::Error::new(e.kind(), e)
+    }
+}
+
+// Attaches registry context to fallible operations.
+pub(crate) trait ResultExt<T> {
+    fn context(self, operation: &'static str, path: &str) -> Result<T>;
+    fn value_context(self, operation: &'static str, path: &str, value: &str) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, operation: &'static str, path: &str) -> Result<T> {
+        self.map_err(|e| e.into().with_context(operation, path, None))
+    }
+
+    fn value_context(self, operation: &'static str, path: &str, value: &str) -> Result<T> {
+        self.map_err(|e| e.into().with_context(operation, path, Some(value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_error_kinds() {
+        let e = Error::from(io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(e, Error::NotFound { .. }));
+
+        let e = Error::from(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(e, Error::PermissionDenied { .. }));
+
+        let e = Error::from(io::Error::from(io::ErrorKind::Interrupted));
+        assert!(matches!(e, Error::Backend { .. }));
+        assert_eq!(e.kind(), io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn io_error_round_trip() {
+        let e = Error::invalid_name("open", "reserved").with_context(
+            "renaming key",
+            "Directory\\shell\\a",
+            None,
+        );
+        let io_error = io::Error::from(e);
+        assert_eq!(io_error.kind(), io::ErrorKind::InvalidInput);
+
+        let e = Error::from(io_error);
+        assert!(matches!(&e, Error::InvalidName { name, .. } if name == "open"));
+        assert_eq!(e.context().path.as_deref(), Some("Directory\\shell\\a"));
+    }
+
+    #[test]
+    fn inner_context_wins() {
+        let result: Result<()> = Err(Error::not_found());
+        let e = result
+            .value_context("reading value", "Directory\\shell\\a", "Icon")
+            .context("snapshotting entry", "Directory\\shell")
+            .unwrap_err();
+
+        assert_eq!(
+            e.context(),
+            &ErrorContext {
+                operation: Some("reading value"),
+                path: Some("Directory\\shell\\a".to_string()),
+                value: Some("Icon".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn display_with_context() {
+        let e = Error::not_found().with_context("opening key", "Directory\\shell\\a", Some("Icon"));
+        assert_eq!(
+            e.to_string(),
+            "Registry key or value does not exist while opening key \
+             at Directory\\shell\\a (value \"Icon\")"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use super::error::{Error, Result};
+use super::notify;
+use super::*;
+use std::collections::BTreeMap;
+use std::io;
+use windows_sys::Win32::Globalization::{GetUserPreferredUILanguages, MUI_LANGUAGE_NAME};
+
+// Value storing the localized labels of an entry as `language=label` strings.
+pub(crate) const LABELS_VALUE: &str = "WinCtxLabels";
+
+/// Language key of the label used when no other language matches
+pub const FALLBACK_LANGUAGE: &str = "*";
+
+impl CtxEntry {
+    /// Gets the entry's localized labels by language (e.g., `"de-DE"`).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Open in", &ActivationType::Folder)?;
+    /// let labels = entry.localized_labels()?;
+    /// ```
+    pub fn localized_labels(&self) -> Result<BTreeMap<String, String>> {
+        let mut labels = BTreeMap::new();
+
+        let Some(RegValue::MultiSz(pairs)) = self.get_value(LABELS_VALUE)? else {
+            return Ok(labels);
+        };
+
+        for pair in pairs {
+            if let Some((language, label)) = pair.split_once('=') {
+                labels.insert(language.to_string(), label.to_string());
+            }
+        }
+
+        Ok(labels)
+    }
+
+    /// Stores localized labels by language (e.g., `"de-DE"`), then shows the
+    /// one best matching the current user's UI languages. Use
+    /// [`FALLBACK_LANGUAGE`] for a label shown when no language matches.
+    /// Labels may be resource references such as `@shell32.dll,-8506`.
+    /// Passing an empty table removes the localized labels and the label.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::new("Open in", &ActivationType::Folder)?;
+    /// entry.set_localized_labels(&BTreeMap::from([
+    ///     ("en-US".to_string(), "Open in".to_string()),
+    ///     ("de-DE".to_string(), "Öffnen in".to_string()),
+    ///     ("*".to_string(), "@C:\\Program Files\\Tool\\tool.dll,-101".to_string()),
+    /// ]))?;
+    /// ```
+    pub fn set_localized_labels(&mut self, labels: &BTreeMap<String, String>) -> Result<()> {
+        let _batch = notify::begin_batch();
+
+        if labels.is_empty() {
+            self.safe_delete_value(LABELS_VALUE)?;
+            return self.set_label(None);
+        }
+
+        let mut pairs = Vec::new();
+
+        for (language, label) in labels {
+            if language.is_empty() || language.contains('=') {
+                return Err(Error::invalid_value(format!(
+                    "invalid label language {language:?}"
+                )));
+            }
+
+            pairs.push(format!("{language}={label}"));
+        }
+
+        self.set_value(LABELS_VALUE, &RegValue::MultiSz(pairs))?;
+        self.apply_localized_label(&ui_languages()?)
+    }
+
+    /// Shows the localized label best matching the given languages, in
+    /// order of preference. Does nothing if the entry has no localized
+    /// labels, and removes the label if none match.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::get(&["Open in"], &ActivationType::Folder)?.unwrap();
+    /// entry.apply_localized_label(&["de-DE"])?;
+    /// ```
+    pub fn apply_localized_label<L: AsRef<str>>(&mut self, languages: &[L]) -> Result<()> {
+        let labels = self.localized_labels()?;
+
+        if labels.is_empty() {
+            return Ok(());
+        }
+
+        let label = select_label(&labels, languages).map(|l| l.to_string());
+
+        if self
+            .get_value("MUIVerb")?
+            .as_ref()
+            .and_then(RegValue::as_str)
+            != label.as_deref()
+        {
+            self.set_label(label.as_deref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Shows the localized labels best matching the current user's UI
+    /// languages on every entry with localized labels. Call this after the
+    /// user's display language changes. Returns the entries updated.
+    /// Entries that fail to read or update, such as machine-wide entries
+    /// without administrator rights, are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entries = CtxEntry::refresh_localized_labels()?;
+    /// ```
+    pub fn refresh_localized_labels() -> Result<Vec<CtxEntry>> {
+        let _batch = notify::begin_batch();
+        let languages = ui_languages()?;
+        let mut updated = Vec::new();
+
+        for entry_type in ActivationType::all_registered()? {
+            for item in CtxEntry::walk_type(&entry_type).flatten() {
+                let mut entry = item.entry;
+
+                if let Ok(true) = entry.refresh_localized_label(&languages) {
+                    updated.push(entry);
+                }
+            }
+        }
+
+        Ok(updated)
+    }
+
+    // Applies the localized label, returning whether the label changed.
+    fn refresh_localized_label(&mut self, languages: &[String]) -> Result<bool> {
+        let before = self.get_value("MUIVerb")?;
+        self.apply_localized_label(languages)?;
+        Ok(self.get_value("MUIVerb")? != before)
+    }
+}
+
+/// Gets the current user's UI languages in order of preference
+/// (e.g., `["de-DE", "en-US"]`).
+///
+/// # Examples
+///
+/// ```no_run
+/// let languages = win_ctx::ui_languages()?;
+/// ```
+pub fn ui_languages() -> Result<Vec<String>> {
+    let mut count = 0;
+    let mut len = 0;
+
+    let ok = unsafe {
+        GetUserPreferredUILanguages(
+            MUI_LANGUAGE_NAME,
+            &mut count,
+            std::ptr::null_mut(),
+            &mut len,
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    let mut buffer = vec![0u16; len as usize];
+    let ok = unsafe {
+        GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, buffer.as_mut_ptr(), &mut len)
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    Ok(buffer
+        .split(|&c| c == 0)
+        .filter(|s| !s.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect())
+}
+
+// Picks the label for the first language with an exact match, then the
+// first with a matching base language (e.g., `de` for `de-AT`), then the
+// fallback label.
+fn select_label<'a, L: AsRef<str>>(
+    labels: &'a BTreeMap<String, String>,
+    languages: &[L],
+) -> Option<&'a str> {
+    let base = |language: &str| language.split('-').next().unwrap_or("").to_lowercase();
+
+    let exact = languages.iter().find_map(|language| {
+        labels
+            .iter()
+            .find(|(l, _)| l.eq_ignore_ascii_case(language.as_ref()))
+    });
+    let similar = || {
+        languages.iter().find_map(|language| {
+            labels
+                .iter()
+                .find(|(l, _)| *l != FALLBACK_LANGUAGE && base(l) == base(language.as_ref()))
+        })
+    };
+
+    exact
+        .or_else(similar)
+        .or_else(|| labels.get_key_value(FALLBACK_LANGUAGE))
+        .map(|(_, label)| label.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("de-DE".to_string(), "Öffnen in".to_string()),
+            ("en-US".to_string(), "Open in".to_string()),
+            ("*".to_string(), "@tool.dll,-101".to_string()),
+        ])
+    }
+
+    #[test]
+    fn select_exact_language() {
+        assert_eq!(select_label(&labels(), &["en-us"]), Some("Open in"));
+        assert_eq!(
+            select_label(&labels(), &["fr-FR", "de-DE"]),
+            Some("Öffnen in")
+        );
+    }
+
+    #[test]
+    fn select_base_language() {
+        assert_eq!(select_label(&labels(), &["de-AT"]), Some("Öffnen in"));
+        assert_eq!(
+            select_label(&labels(), &["en-GB", "de-DE"]),
+            Some("Öffnen in")
+        );
+    }
+
+    #[test]
+    fn select_fallback() {
+        assert_eq!(select_label(&labels(), &["fr-FR"]), Some("@tool.dll,-101"));
+
+        let mut labels = labels();
+        labels.remove("*");
+        assert_eq!(select_label(&labels, &["fr-FR"]), None);
+        assert_eq!(select_label(&labels, &[] as &[&str]), None);
+    }
+}
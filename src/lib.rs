@@ -1,7 +1,28 @@
 //! ## Features
 //!
 //! - Create and edit context menu entries and sub-entries
-//! - Toggle the pre-Windows 11 context menu
+//! - Order sub-entries independently of their labels
+//! - Localize labels per UI language, including resource references
+//! - Read and write any registry value on an entry with typed values
+//! - Tolerate values written by other tools and normalize them
+//! - Validate entries and report problems with fix hints
+//! - Repair inconsistent entries, with a dry run to review fixes first
+//! - Find and remove or disable entries left behind by uninstalled apps
+//! - Test against a sandboxed registry root with stock Windows fixtures
+//!   (`testing` feature)
+//! - Move and copy entries between parents and activation types
+//! - Snapshot, restore and compare whole entry trees
+//! - Render entries as text trees for logs and snapshot tests
+//! - Reference entries by textual addresses such as `folder:Open in/Terminal`
+//! - Walk entry trees lazily with pruning and depth limits
+//! - Read entry properties in one pass and reuse open keys with minimal access
+//! - Search entries across all activation types by name, command, flags and more
+//! - Tag entries with an owner and clean them up by owner
+//! - Toggle the pre-Windows 11 context menu per user or machine and restore
+//!   its previous state
+//! - Notify Explorer of changes and restart it
+//! - Generate package manifests for the Windows 11 context menu
+//! - Manage entries from the command line with the `winctx` tool (`cli` feature)
 //!
 //! ## Basic examples
 //!
@@ -15,11 +36,9 @@
 //!     "Open in terminal",
 //!     &ActivationType::Folder,
 //!     &EntryOptions {
-//!         command: Some("cmd /s /k pushd \"%V\""),
-//!         icon: Some("C:\\Windows\\System32\\cmd.exe"),
-//!         position: None,
-//!         separator: None,
-//!         extended: false,
+//!         command: Some("cmd /s /k pushd \"%V\"".to_string()),
+//!         icon: Some("C:\\Windows\\System32\\cmd.exe".to_string()),
+//!         ..Default::default()
 //!     }
 //! )?;
 //! ```
@@ -45,22 +64,87 @@
 //! child_2.set_icon(Some("C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe"))?;
 //! ```
 //!
+//! The same menu can be created in one call with a builder, which validates
+//! every name before creating anything.
+//!
+//! ```no_run
+//! use win_ctx::{CtxEntry, ActivationType};
+//!
+//! let built = CtxEntry::builder("Open directory in", &ActivationType::Background)
+//!     .child(|c| {
+//!         c.name("Terminal")
+//!             .command("cmd /s /k pushd \"%V\"")
+//!             .icon("C:\\Windows\\System32\\cmd.exe")
+//!     })
+//!     .child(|c| {
+//!         c.name("Powershell")
+//!             .command("powershell -noexit -command Set-Location -literalPath '%V'")
+//!             .icon("C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe")
+//!     })
+//!     .build()?;
+//! ```
+//!
 //! ## Errors
 //!
 //! It's possible that an entry's underlying registry key goes out of sync,
-//! so most `CtxEntry` functions verify this and return a [`std::io::Result`].
+//! so most `CtxEntry` functions verify this and return a [`Result`].
 //!
-//! Errors will have an [`ErrorKind`] of either:
+//! Errors are one of:
+//! - `NotFound` for operations on missing keys and values,
+//! - `AlreadyExists` for conflicting entry names,
 //! - `PermissionDenied` for insufficient privileges,
-//! - `InvalidValue` for invalid entry renames, or
-//! - `NotFound` for operations on missing keys and values.
+//! - `InvalidName` for invalid entry names,
+//! - `InvalidValue` for malformed arguments and values, or
+//! - `Backend` for any other registry error.
 //!
-//! [`ErrorKind`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html
-//! [`std::io::Result`]: https://doc.rust-lang.org/std/io/type.Result.html
+//! Each error carries the operation, registry path and value name involved,
+//! and converts to and from [`std::io::Error`].
 
+pub use address::EntryAddress;
+pub use builder::{BuiltEntry, EntryBuilder};
+pub use diff::{EntryChange, FieldChange, diff_entries, diff_report};
 pub use entry::*;
-pub use utils::toggle_classic_menu;
+pub use error::{Error, ErrorContext, Result};
+pub use labels::{FALLBACK_LANGUAGE, ui_languages};
+pub use manifest::{ModernVerb, PackageManifest};
+pub use normalize::ValueWarning;
+pub use notify::{Notifier, ShellNotifier, batch, restart_explorer, set_notifier};
+pub use orphans::{Orphan, OrphanScan, orphan_report};
+pub use properties::EntryProperties;
+pub use query::{Query, QueryMatch};
+pub use render::{TreeField, TreeOptions};
+pub use repair::{Repair, RepairKind, RepairOptions, repair_report};
+pub use snapshot::{EntrySnapshot, KeySnapshot};
+pub use transfer::{ConflictPolicy, Destination};
+pub use utils::{
+    ClassicMenuState, Scope, classic_menu_state, set_classic_menu, toggle_classic_menu,
+};
+pub use validate::{Diagnostic, DiagnosticKind, Severity, Validator};
+pub use value::RegValue;
+pub use walk::{Walk, WalkEntry};
 
+mod address;
+mod builder;
+mod command;
+mod diff;
 mod entry;
+mod error;
+mod labels;
+mod manifest;
+mod normalize;
+mod notify;
+mod orphans;
+mod owner;
 mod path;
+mod properties;
+mod query;
+mod render;
+mod repair;
+mod snapshot;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transfer;
 mod utils;
+mod validate;
+mod value;
+mod walk;
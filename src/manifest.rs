@@ -0,0 +1,491 @@
+use super::builder::EntryBuilder;
+use super::error::{Error, Result};
+use super::path::get_base_path;
+use super::{ActivationType, CtxEntry};
+
+/// A verb in the Windows 11 context menu, handled by a packaged
+/// `IExplorerCommand` implementation
+#[derive(Debug, Clone)]
+pub struct ModernVerb {
+    /// Verb identifier (must be unique per activation type)
+    pub id: String,
+    /// Visible label, to be returned by the implementation's `GetTitle`
+    pub label: String,
+    /// CLSID of the `IExplorerCommand` implementation
+    pub clsid: String,
+    /// Entry activation type
+    pub entry_type: ActivationType,
+}
+
+/// Sparse package manifest declaring Windows 11 context menu verbs
+#[derive(Debug, Clone)]
+pub struct PackageManifest {
+    /// Package identity name (e.g., `Company.Product`)
+    pub name: String,
+    /// Package publisher (e.g., `CN=Company`), must match the signing certificate
+    pub publisher: String,
+    /// Package version in `major.minor.build.revision` form
+    pub version: String,
+    /// Display name of the package
+    pub display_name: String,
+    /// Display name of the publisher
+    pub publisher_display_name: String,
+    /// Path to the package logo, relative to the external location
+    pub logo: String,
+    /// Path to the application executable, relative to the external location
+    pub executable: String,
+    /// Path to the DLL implementing the verbs, relative to the external location
+    pub com_server: String,
+    /// Verbs to declare
+    pub verbs: Vec<ModernVerb>,
+}
+
+impl ModernVerb {
+    /// Creates a verb for an existing classic entry, with the same label and
+    /// activation type. Fails if the label has no ASCII letters or digits to
+    /// derive the verb ID from.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::get(&["Open in terminal"], &ActivationType::Folder)?;
+    /// let verb = ModernVerb::from_entry(&entry, "{4C2E3F5A-2B1D-4E8F-9A6B-7C0D1E2F3A4B}")?;
+    /// assert_eq!(verb.id, "Openinterminal");
+    /// ```
+    pub fn from_entry(entry: &CtxEntry, clsid: &str) -> Result<ModernVerb> {
+        ModernVerb::new(&entry.label()?, entry.entry_type(), clsid)
+    }
+
+    /// Creates a verb from an [`EntryBuilder`], so that one definition can
+    /// produce both the classic entry and the modern verb.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let builder = CtxEntry::builder("Open in terminal", &ActivationType::Folder)
+    ///     .command("wt -d \"%V\"");
+    /// let verb = ModernVerb::from_builder(&builder, "{4C2E3F5A-2B1D-4E8F-9A6B-7C0D1E2F3A4B}")?;
+    /// builder.build()?;
+    /// ```
+    pub fn from_builder(builder: &EntryBuilder, clsid: &str) -> Result<ModernVerb> {
+        let label = builder.label.as_deref().unwrap_or(&builder.name);
+        ModernVerb::new(label, &builder.entry_type, clsid)
+    }
+
+    /// Creates a verb from a label. The verb ID is derived from the ASCII
+    /// alphanumeric characters of the label, and creation fails if there are
+    /// none.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let verb = ModernVerb::new(
+    ///     "Open in terminal",
+    ///     &ActivationType::Folder,
+    ///     "{4C2E3F5A-2B1D-4E8F-9A6B-7C0D1E2F3A4B}",
+    /// )?;
+    /// assert_eq!(verb.id, "Openinterminal");
+    /// ```
+    pub fn new(label: &str, entry_type: &ActivationType, clsid: &str) -> Result<ModernVerb> {
+        let id: String = label
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+
+        if id.is_empty() {
+            return Err(Error::invalid_name(
+                label,
+                "no ASCII letters or digits for a verb ID",
+            ));
+        }
+
+        Ok(ModernVerb {
+            id,
+            label: label.to_string(),
+            clsid: clsid.to_string(),
+            entry_type: entry_type.clone(),
+        })
+    }
+
+    /// Gets the manifest item type the verb is declared under.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let verb = ModernVerb::new("Format", &ActivationType::File(".rs".to_string()), clsid)?;
+    /// assert_eq!(verb.item_type(), ".rs");
+    /// ```
+    pub fn item_type(&self) -> String {
+        get_base_path(&self.entry_type)
+    }
+}
+
+impl PackageManifest {
+    /// Generates the `AppxManifest.xml` contents for the package. Fails if a
+    /// verb ID is empty or used twice for the same item type, ignoring case.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let manifest = PackageManifest {
+    ///     name: "Company.Product".to_string(),
+    ///     publisher: "CN=Company".to_string(),
+    ///     version: "1.0.0.0".to_string(),
+    ///     display_name: "Product".to_string(),
+    ///     publisher_display_name: "Company".to_string(),
+    ///     logo: "logo.png".to_string(),
+    ///     executable: "product.exe".to_string(),
+    ///     com_server: "product_menu.dll".to_string(),
+    ///     verbs: vec![verb],
+    /// };
+    /// std::fs::write("AppxManifest.xml", manifest.to_xml()?)?;
+    /// ```
+    pub fn to_xml(&self) -> Result<String> {
+        self.check_verb_ids()?;
+        let mut xml = String::new();
+
+        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str(concat!(
+            "<Package\n",
+            "  xmlns=\"http://schemas.microsoft.com/appx/manifest/foundation/windows10\"\n",
+            "  xmlns:uap=\"http://schemas.microsoft.com/appx/manifest/uap/windows10\"\n",
+            "  xmlns:uap10=\"http://schemas.microsoft.com/appx/manifest/uap/windows10/10\"\n",
+            "  xmlns:desktop4=\"http://schemas.microsoft.com/appx/manifest/desktop/windows10/4\"\n",
+            "  xmlns:desktop5=\"http://schemas.microsoft.com/appx/manifest/desktop/windows10/5\"\n",
+            "  xmlns:com=\"http://schemas.microsoft.com/appx/manifest/com/windows10\"\n",
+            "  xmlns:rescap=\"http://schemas.microsoft.com/appx/manifest/foundation/windows10/restrictedcapabilities\"\n",
+            "  IgnorableNamespaces=\"uap uap10 desktop4 desktop5 com rescap\">\n",
+        ));
+        xml.push_str(&format!(
+            "  <Identity Name=\"{}\" Publisher=\"{}\" Version=\"{}\" />\n",
+            escape(&self.name),
+            escape(&self.publisher),
+            escape(&self.version),
+        ));
+        xml.push_str("  <Properties>\n");
+        xml.push_str(&format!(
+            "    <DisplayName>{}</DisplayName>\n",
+            escape(&self.display_name)
+        ));
+        xml.push_str(&format!(
+            "    <PublisherDisplayName>{}</PublisherDisplayName>\n",
+            escape(&self.publisher_display_name)
+        ));
+        xml.push_str(&format!("    <Logo>{}</Logo>\n", escape(&self.logo)));
+        xml.push_str("    <uap10:AllowExternalContent>true</uap10:AllowExternalContent>\n");
+        xml.push_str("  </Properties>\n");
+        xml.push_str(concat!(
+            "  <Resources>\n",
+            "    <Resource Language=\"en-us\" />\n",
+            "  </Resources>\n",
+            "  <Dependencies>\n",
+            "    <TargetDeviceFamily Name=\"Windows.Desktop\" MinVersion=\"10.0.19041.0\" MaxVersionTested=\"10.0.22621.0\" />\n",
+            "  </Dependencies>\n",
+            "  <Capabilities>\n",
+            "    <rescap:Capability Name=\"runFullTrust\" />\n",
+            "    <rescap:Capability Name=\"unvirtualizedResources\" />\n",
+            "  </Capabilities>\n",
+            "  <Applications>\n",
+        ));
+        xml.push_str(&format!(
+            "    <Application Id=\"App\" Executable=\"{}\" uap10:TrustLevel=\"mediumIL\" uap10:RuntimeBehavior=\"win32App\">\n",
+            escape(&self.executable)
+        ));
+        xml.push_str(&format!(
+            "      <uap:VisualElements AppListEntry=\"none\" DisplayName=\"{0}\" Description=\"{0}\" BackgroundColor=\"transparent\" Square150x150Logo=\"{1}\" Square44x44Logo=\"{1}\" />\n",
+            escape(&self.display_name),
+            escape(&self.logo),
+        ));
+        xml.push_str("      <Extensions>\n");
+        self.push_context_menus(&mut xml);
+        self.push_com_server(&mut xml);
+        xml.push_str("      </Extensions>\n");
+        xml.push_str("    </Application>\n");
+        xml.push_str("  </Applications>\n");
+        xml.push_str("</Package>\n");
+
+        Ok(xml)
+    }
+
+    fn check_verb_ids(&self) -> Result<()> {
+        for (i, verb) in self.verbs.iter().enumerate() {
+            if verb.id.is_empty() {
+                return Err(Error::invalid_value("empty verb ID"));
+            }
+
+            let item_type = verb.item_type();
+            let duplicate = self.verbs[..i].iter().any(|v| {
+                v.id.eq_ignore_ascii_case(&verb.id)
+                    && v.item_type().eq_ignore_ascii_case(&item_type)
+            });
+
+            if duplicate {
+                return Err(Error::invalid_value(format!(
+                    "duplicate verb ID {:?} for item type {item_type:?}",
+                    verb.id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push_context_menus(&self, xml: &mut String) {
+        if self.verbs.is_empty() {
+            return;
+        }
+
+        xml.push_str(
+            "        <desktop4:Extension Category=\"windows.fileExplorerContextMenus\">\n",
+        );
+        xml.push_str("          <desktop4:FileExplorerContextMenus>\n");
+
+        for item_type in self.item_types() {
+            xml.push_str(&format!(
+                "            <desktop5:ItemType Type=\"{}\">\n",
+                escape(&item_type)
+            ));
+
+            for verb in self
+                .verbs
+                .iter()
+                .filter(|v| v.item_type().eq_ignore_ascii_case(&item_type))
+            {
+                xml.push_str(&format!(
+                    "              <desktop5:Verb Id=\"{}\" Clsid=\"{}\" />\n",
+                    escape(&verb.id),
+                    escape(trim_clsid(&verb.clsid)),
+                ));
+            }
+
+            xml.push_str("            </desktop5:ItemType>\n");
+        }
+
+        xml.push_str("          </desktop4:FileExplorerContextMenus>\n");
+        xml.push_str("        </desktop4:Extension>\n");
+    }
+
+    fn push_com_server(&self, xml: &mut String) {
+        let mut clsids: Vec<&str> = Vec::new();
+
+        for verb in &self.verbs {
+            let clsid = trim_clsid(&verb.clsid);
+            if !clsids.iter().any(|c| c.eq_ignore_ascii_case(clsid)) {
+                clsids.push(clsid);
+            }
+        }
+
+        if clsids.is_empty() {
+            return;
+        }
+
+        xml.push_str("        <com:Extension Category=\"windows.comServer\">\n");
+        xml.push_str("          <com:ComServer>\n");
+        xml.push_str(&format!(
+            "            <com:SurrogateServer DisplayName=\"{}\">\n",
+            escape(&self.display_name)
+        ));
+
+        for clsid in clsids {
+            xml.push_str(&format!(
+                "              <com:Class Id=\"{}\" Path=\"{}\" ThreadingModel=\"STA\" />\n",
+                escape(clsid),
+                escape(&self.com_server),
+            ));
+        }
+
+        xml.push_str("            </com:SurrogateServer>\n");
+        xml.push_str("          </com:ComServer>\n");
+        xml.push_str("        </com:Extension>\n");
+    }
+
+    // Item types in order of first appearance, ignoring case like file
+    // associations do.
+    fn item_types(&self) -> Vec<String> {
+        let mut item_types: Vec<String> = Vec::new();
+
+        for item_type in self.verbs.iter().map(|v| v.item_type()) {
+            if !item_types
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(&item_type))
+            {
+                item_types.push(item_type);
+            }
+        }
+
+        item_types
+    }
+}
+
+// Manifests expect bare GUIDs without braces.
+fn trim_clsid(clsid: &str) -> &str {
+    clsid.trim_start_matches('{').trim_end_matches('}')
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLSID_1: &str = "{4C2E3F5A-2B1D-4E8F-9A6B-7C0D1E2F3A4B}";
+    const CLSID_2: &str = "{9D8C7B6A-5F4E-4D3C-8B2A-1F0E9D8C7B6A}";
+
+    fn manifest(verbs: Vec<ModernVerb>) -> PackageManifest {
+        PackageManifest {
+            name: "Company.Product".to_string(),
+            publisher: "CN=Company".to_string(),
+            version: "1.0.0.0".to_string(),
+            display_name: "Product & Co".to_string(),
+            publisher_display_name: "Company".to_string(),
+            logo: "logo.png".to_string(),
+            executable: "product.exe".to_string(),
+            com_server: "menu.dll".to_string(),
+            verbs,
+        }
+    }
+
+    #[test]
+    fn verb_from_label() {
+        let verb = ModernVerb::new("Open in terminal", &ActivationType::Folder, CLSID_1).unwrap();
+        assert_eq!(verb.id, "Openinterminal");
+        assert_eq!(verb.label, "Open in terminal");
+        assert_eq!(verb.item_type(), "Directory");
+    }
+
+    #[test]
+    fn verb_from_builder() {
+        let builder = CtxEntry::builder("01_Terminal", &ActivationType::Background);
+        let verb = ModernVerb::from_builder(&builder, CLSID_1).unwrap();
+        assert_eq!(verb.id, "01Terminal");
+        assert_eq!(verb.item_type(), "Directory\\Background");
+
+        let verb = ModernVerb::from_builder(&builder.label("Open terminal"), CLSID_1).unwrap();
+        assert_eq!(verb.id, "Openterminal");
+        assert_eq!(verb.label, "Open terminal");
+    }
+
+    #[test]
+    fn item_types_from_activation_types() {
+        let file = ModernVerb::new("A", &ActivationType::File("*".to_string()), CLSID_1).unwrap();
+        let ext = ModernVerb::new("A", &ActivationType::File(".rs".to_string()), CLSID_1).unwrap();
+        let bg = ModernVerb::new("A", &ActivationType::Background, CLSID_1).unwrap();
+
+        assert_eq!(file.item_type(), "*");
+        assert_eq!(ext.item_type(), ".rs");
+        assert_eq!(bg.item_type(), "Directory\\Background");
+    }
+
+    #[test]
+    fn verbs_grouped_by_item_type() {
+        let xml = manifest(vec![
+            ModernVerb::new("One", &ActivationType::Folder, CLSID_1).unwrap(),
+            ModernVerb::new("Two", &ActivationType::Background, CLSID_2).unwrap(),
+            ModernVerb::new("Three", &ActivationType::Folder, CLSID_2).unwrap(),
+        ])
+        .to_xml()
+        .unwrap();
+
+        assert_eq!(
+            xml.matches("<desktop5:ItemType Type=\"Directory\">")
+                .count(),
+            1
+        );
+        assert_eq!(
+            xml.matches("<desktop5:ItemType Type=\"Directory\\Background\">")
+                .count(),
+            1
+        );
+        assert!(xml.contains(concat!(
+            "            <desktop5:ItemType Type=\"Directory\">\n",
+            "              <desktop5:Verb Id=\"One\" Clsid=\"4C2E3F5A-2B1D-4E8F-9A6B-7C0D1E2F3A4B\" />\n",
+            "              <desktop5:Verb Id=\"Three\" Clsid=\"9D8C7B6A-5F4E-4D3C-8B2A-1F0E9D8C7B6A\" />\n",
+            "            </desktop5:ItemType>\n",
+        )));
+    }
+
+    #[test]
+    fn com_classes_deduplicated() {
+        let xml = manifest(vec![
+            ModernVerb::new("One", &ActivationType::Folder, CLSID_1).unwrap(),
+            ModernVerb::new("Two", &ActivationType::Background, CLSID_1).unwrap(),
+        ])
+        .to_xml()
+        .unwrap();
+
+        assert_eq!(xml.matches("<com:Class ").count(), 1);
+        assert!(xml.contains(
+            "<com:Class Id=\"4C2E3F5A-2B1D-4E8F-9A6B-7C0D1E2F3A4B\" Path=\"menu.dll\" ThreadingModel=\"STA\" />"
+        ));
+    }
+
+    #[test]
+    fn no_verbs_no_extensions() {
+        let xml = manifest(Vec::new()).to_xml().unwrap();
+        assert!(!xml.contains("desktop4:Extension"));
+        assert!(!xml.contains("com:Extension"));
+    }
+
+    #[test]
+    fn values_are_escaped() {
+        let xml = manifest(Vec::new()).to_xml().unwrap();
+        assert!(xml.contains("<DisplayName>Product &amp; Co</DisplayName>"));
+    }
+
+    #[test]
+    fn verb_id_from_non_ascii_name() {
+        ModernVerb::new("Открыть", &ActivationType::Folder, CLSID_1).unwrap_err();
+        let verb = ModernVerb::new("Öffnen 2", &ActivationType::Folder, CLSID_1).unwrap();
+        assert_eq!(verb.id, "ffnen2");
+    }
+
+    #[test]
+    fn duplicate_verb_ids() {
+        let verb = |name, entry_type| ModernVerb::new(name, &entry_type, CLSID_1).unwrap();
+
+        manifest(vec![
+            verb("Open in", ActivationType::Folder),
+            verb("Open-in", ActivationType::Folder),
+        ])
+        .to_xml()
+        .unwrap_err();
+        manifest(vec![
+            verb("Open in", ActivationType::Folder),
+            verb("Open In", ActivationType::Background),
+        ])
+        .to_xml()
+        .unwrap();
+    }
+
+    #[test]
+    fn item_types_ignore_case() {
+        let verb = |name, ext: &str| {
+            ModernVerb::new(name, &ActivationType::File(ext.to_string()), CLSID_1).unwrap()
+        };
+
+        let xml = manifest(vec![verb("One", ".rs"), verb("Two", ".RS")])
+            .to_xml()
+            .unwrap();
+        assert_eq!(xml.matches("<desktop5:ItemType ").count(), 1);
+        assert_eq!(xml.matches("<desktop5:Verb ").count(), 2);
+
+        manifest(vec![verb("One", ".rs"), verb("one", ".RS")])
+            .to_xml()
+            .unwrap_err();
+    }
+}
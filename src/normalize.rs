@@ -0,0 +1,142 @@
+use super::entry::{parse_position, position_str};
+use super::error::Result;
+use super::notify;
+use super::owner::OWNER_VALUE;
+use super::*;
+
+// Values whose presence alone turns a flag on.
+const FLAG_VALUES: [&str; 4] = [
+    "Extended",
+    "SeparatorBefore",
+    "SeparatorAfter",
+    "LegacyDisable",
+];
+
+// Values read as strings.
+const STRING_VALUES: [&str; 3] = ["MUIVerb", "Icon", OWNER_VALUE];
+
+/// Known value stored in a form other than the one this crate writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueWarning {
+    /// Value name as stored
+    pub name: String,
+    /// Value as stored
+    pub value: RegValue,
+    /// Form the value is normalized to, or `None` if it is not recognized
+    /// and is left as is
+    pub canonical: Option<RegValue>,
+}
+
+impl CtxEntry {
+    /// Lists known values stored in a non-canonical form, such as
+    /// `Extended` stored as a `REG_DWORD` or `Position` stored as `top`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::get(&["Open directory in"], &ActivationType::Background)?.unwrap();
+    ///
+    /// for warning in entry.value_warnings()? {
+    ///     println!("{}: {:?}", warning.name, warning.value);
+    /// }
+    /// ```
+    pub fn value_warnings(&self) -> Result<Vec<ValueWarning>> {
+        Ok(self
+            .values()?
+            .into_iter()
+            .filter_map(|(name, value)| check_value(name, value))
+            .collect())
+    }
+
+    /// Rewrites known values stored in a non-canonical form and returns
+    /// the warnings found. Values that are not recognized are left as is.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::get(&["Open directory in"], &ActivationType::Background)?.unwrap();
+    /// let fixed = entry.normalize()?;
+    /// ```
+    pub fn normalize(&mut self) -> Result<Vec<ValueWarning>> {
+        let _batch = notify::begin_batch();
+        let warnings = self.value_warnings()?;
+
+        for warning in &warnings {
+            if let Some(canonical) = &warning.canonical {
+                self.set_value(&warning.name, canonical)?;
+            }
+        }
+
+        Ok(warnings)
+    }
+}
+
+fn check_value(name: String, value: RegValue) -> Option<ValueWarning> {
+    let is = |known: &str| name.eq_ignore_ascii_case(known);
+
+    let canonical = if FLAG_VALUES.iter().any(|v| is(v)) {
+        Some(RegValue::Sz(String::new()))
+    } else if is("Position") {
+        value
+            .as_str()
+            .and_then(parse_position)
+            .map(|p| RegValue::Sz(position_str(&p).to_string()))
+    } else if STRING_VALUES.iter().any(|v| is(v)) && value.as_str().is_none() {
+        // Strings stored with another type cannot be converted reliably.
+        None
+    } else {
+        return None;
+    };
+
+    if canonical.as_ref() == Some(&value) {
+        return None;
+    }
+
+    Some(ValueWarning {
+        name,
+        value,
+        canonical,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(name: &str, value: RegValue) -> Option<Option<RegValue>> {
+        check_value(name.to_string(), value).map(|w| w.canonical)
+    }
+
+    #[test]
+    fn canonical_values() {
+        assert_eq!(check("Extended", RegValue::Sz(String::new())), None);
+        assert_eq!(check("Position", RegValue::Sz("Top".to_string())), None);
+        assert_eq!(
+            check("Icon", RegValue::ExpandSz("%icon%".to_string())),
+            None
+        );
+        assert_eq!(check("Subcommands", RegValue::Dword(1)), None);
+    }
+
+    #[test]
+    fn non_canonical_values() {
+        let empty = Some(Some(RegValue::Sz(String::new())));
+        assert_eq!(check("extended", RegValue::Dword(1)), empty);
+        assert_eq!(check("SeparatorAfter", RegValue::None), empty);
+        assert_eq!(check("LegacyDisable", RegValue::Dword(1)), empty);
+        assert_eq!(
+            check("SeparatorBefore", RegValue::Sz("1".to_string())),
+            empty
+        );
+
+        assert_eq!(
+            check("Position", RegValue::Sz("bottom".to_string())),
+            Some(Some(RegValue::Sz("Bottom".to_string())))
+        );
+        assert_eq!(
+            check("Position", RegValue::Sz("Middle".to_string())),
+            Some(None)
+        );
+        assert_eq!(check("MUIVerb", RegValue::Dword(1)), Some(None));
+    }
+}
@@ -0,0 +1,195 @@
+use super::error::Result;
+use std::cell::Cell;
+use std::io;
+use std::process::Command;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use windows_sys::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+use windows_sys::Win32::UI::Shell::{SHCNE_ASSOCCHANGED, SHCNF_IDLIST, SHChangeNotify};
+
+/// Receives a notification after the registry is modified
+pub trait Notifier: Send + Sync {
+    fn notify(&self);
+}
+
+/// Notifier that tells Explorer that file associations changed, so that
+/// menu changes are picked up without restarting it. Does nothing for
+/// changes made while a test sandbox is active.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShellNotifier;
+
+impl Notifier for ShellNotifier {
+    fn notify(&self) {
+        // Sandboxed changes never reach Explorer.
+        #[cfg(feature = "testing")]
+        if super::testing::sandbox_active() {
+            return;
+        }
+
+        unsafe {
+            SHChangeNotify(
+                SHCNE_ASSOCCHANGED as i32,
+                SHCNF_IDLIST,
+                std::ptr::null(),
+                std::ptr::null(),
+            );
+        }
+    }
+}
+
+static NOTIFIER: RwLock<Option<Arc<dyn Notifier>>> = RwLock::new(None);
+
+thread_local! {
+    // Nesting depth of batches on this thread and whether anything changed.
+    static BATCH: Cell<(usize, bool)> = const { Cell::new((0, false)) };
+}
+
+/// Sets the notifier called after modifications. No notifier is set by
+/// default.
+///
+/// # Examples
+///
+/// ```no_run
+/// win_ctx::set_notifier(Some(Arc::new(ShellNotifier)));
+/// ```
+pub fn set_notifier(notifier: Option<Arc<dyn Notifier>>) {
+    *NOTIFIER.write().unwrap_or_else(|e| e.into_inner()) = notifier;
+}
+
+/// Runs the given function, notifying at most once at the end no matter how
+/// many modifications it makes.
+///
+/// # Examples
+///
+/// ```no_run
+/// win_ctx::batch(|| -> win_ctx::Result<()> {
+///     let entry = CtxEntry::new("Open directory in", &ActivationType::Background)?;
+///     entry.new_child("Terminal")?;
+///     entry.new_child("Powershell")?;
+///     Ok(())
+/// })?;
+/// ```
+pub fn batch<T>(f: impl FnOnce() -> T) -> T {
+    let _batch = begin_batch();
+    f()
+}
+
+/// Restarts Explorer in the current session, waiting for it to exit before
+/// starting it again. Explorer instances of other signed-in users are left
+/// running.
+/// Fails with [`ErrorKind::TimedOut`](std::io::ErrorKind::TimedOut) if it
+/// is still running after 10 seconds.
+///
+/// # Examples
+///
+/// ```no_run
+/// win_ctx::toggle_classic_menu(true)?;
+/// win_ctx::restart_explorer()?;
+/// ```
+pub fn restart_explorer() -> Result<()> {
+    // Other users' shells on the same machine must be left alone.
+    let session = format!("session eq {}", current_session()?);
+
+    // Fails if Explorer is not running, which is fine.
+    let _ = Command::new("taskkill")
+        .args(["/f", "/fi", &session, "/im", "explorer.exe"])
+        .output()?;
+
+    // Killing is asynchronous, and a new instance started too early would
+    // hand off to the old one and exit.
+    let deadline = Instant::now() + EXPLORER_EXIT_TIMEOUT;
+
+    while explorer_running(&session)? {
+        if Instant::now() >= deadline {
+            return Err(
+                io::Error::new(io::ErrorKind::TimedOut, "explorer.exe did not exit").into(),
+            );
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    Command::new("explorer.exe").spawn()?;
+    Ok(())
+}
+
+const EXPLORER_EXIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn current_session() -> Result<u32> {
+    let mut session = 0;
+
+    if unsafe { ProcessIdToSessionId(std::process::id(), &mut session) } == 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    Ok(session)
+}
+
+// `session` is a tasklist filter selecting the current session.
+fn explorer_running(session: &str) -> Result<bool> {
+    let output = Command::new("tasklist")
+        .args(["/fi", "imagename eq explorer.exe", "/fi", session])
+        .args(["/fo", "csv", "/nh"])
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Without a match, tasklist prints an informational message instead.
+    Ok(stdout.to_ascii_lowercase().contains("\"explorer.exe\""))
+}
+
+// Ends a batch when dropped.
+pub(crate) struct BatchGuard;
+
+pub(crate) fn begin_batch() -> BatchGuard {
+    BATCH.with(|b| {
+        let (depth, changed) = b.get();
+        b.set((depth + 1, changed));
+    });
+    BatchGuard
+}
+
+impl Drop for BatchGuard {
+    fn drop(&mut self) {
+        let notify = BATCH.with(|b| {
+            let (depth, changed) = b.get();
+
+            if depth == 1 {
+                b.set((0, false));
+                changed
+            } else {
+                b.set((depth - 1, changed));
+                false
+            }
+        });
+
+        if notify {
+            notify_now();
+        }
+    }
+}
+
+// Records a modification, notifying now unless in a batch.
+pub(crate) fn changed() {
+    let in_batch = BATCH.with(|b| {
+        let (depth, _) = b.get();
+
+        if depth > 0 {
+            b.set((depth, true));
+        }
+
+        depth > 0
+    });
+
+    if !in_batch {
+        notify_now();
+    }
+}
+
+fn notify_now() {
+    let notifier = NOTIFIER.read().unwrap_or_else(|e| e.into_inner()).clone();
+
+    if let Some(notifier) = notifier {
+        notifier.notify();
+    }
+}
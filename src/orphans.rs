@@ -0,0 +1,170 @@
+use super::command::missing_executable;
+use super::error::Result;
+use super::notify;
+use super::query::glob_match;
+use super::*;
+
+/// Scan for entries whose command runs an executable that does not exist,
+/// such as verbs left behind by uninstalled apps. Disabled entries and
+/// executables that cannot be checked (e.g., `"%1"`) are skipped.
+///
+/// Executables are resolved like Windows does when starting a process:
+/// quoted paths, environment variables, `PATH` and App Paths.
+///
+/// # Examples
+///
+/// ```no_run
+/// let orphans = OrphanScan::new().exclude("folder:Git*").run()?;
+/// print!("{}", win_ctx::orphan_report(&orphans));
+///
+/// for orphan in orphans {
+///     orphan.remove()?;
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OrphanScan {
+    entry_types: Option<Vec<ActivationType>>,
+    exclude: Vec<String>,
+}
+
+/// Entry found by an [`OrphanScan`]
+#[derive(Debug)]
+pub struct Orphan {
+    pub entry: CtxEntry,
+    pub address: EntryAddress,
+    /// Command of the entry
+    pub command: String,
+    /// Executable that was not found, as written in the command
+    pub executable: String,
+}
+
+impl OrphanScan {
+    /// Creates a scan of every entry.
+    pub fn new() -> OrphanScan {
+        OrphanScan::default()
+    }
+
+    /// Limits the scan to the given activation type. Can be called multiple
+    /// times. By default, all registered activation types are scanned.
+    pub fn entry_type(mut self, entry_type: &ActivationType) -> OrphanScan {
+        self.entry_types
+            .get_or_insert_with(Vec::new)
+            .push(entry_type.clone());
+        self
+    }
+
+    /// Skips entries whose address (e.g., `folder:Open in/Terminal`)
+    /// matches a case-insensitive pattern, where `*` matches any characters
+    /// and `?` matches a single character. The children of skipped entries
+    /// are skipped too. Can be called multiple times.
+    pub fn exclude(mut self, pattern: &str) -> OrphanScan {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    /// Runs the scan. Results are ordered by activation type, then
+    /// depth-first in menu order. The children of orphans are not scanned,
+    /// as removing or disabling an orphan also removes or hides them.
+    /// Entries that fail to read are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let orphans = OrphanScan::new().entry_type(&ActivationType::Background).run()?;
+    /// ```
+    pub fn run(&self) -> Result<Vec<Orphan>> {
+        let entry_types = match &self.entry_types {
+            Some(entry_types) => entry_types.clone(),
+            None => ActivationType::all_registered()?,
+        };
+
+        let mut orphans = Vec::new();
+
+        for entry_type in entry_types {
+            let mut walk = CtxEntry::walk_type(&entry_type);
+
+            while let Some(item) = walk.next() {
+                let Ok(WalkEntry { entry, .. }) = item else {
+                    continue;
+                };
+                let address = entry.address();
+                let shown = address.to_string();
+
+                if self.exclude.iter().any(|p| glob_match(p, &shown))
+                    || entry.disabled().unwrap_or(true)
+                {
+                    walk.skip_children();
+                    continue;
+                }
+
+                let Ok(Some(command)) = entry.command() else {
+                    continue;
+                };
+
+                if let Some(executable) = missing_executable(&command) {
+                    walk.skip_children();
+                    orphans.push(Orphan {
+                        executable: executable.to_string(),
+                        command,
+                        address,
+                        entry,
+                    });
+                }
+            }
+        }
+
+        Ok(orphans)
+    }
+}
+
+impl Orphan {
+    /// Deletes the entry and any children. A parent left without children
+    /// no longer shows an empty submenu.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// for orphan in OrphanScan::new().run()? {
+    ///     orphan.remove()?;
+    /// }
+    /// ```
+    pub fn remove(self) -> Result<()> {
+        let _batch = notify::begin_batch();
+        let parent = self.entry.parent()?;
+        self.entry.delete()?;
+
+        match parent {
+            Some(parent) => parent.remove_empty_subcommands(),
+            None => Ok(()),
+        }
+    }
+
+    /// Hides the entry and any children from the context menu with the
+    /// `LegacyDisable` value, so it can be shown again if the executable
+    /// comes back.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// for mut orphan in OrphanScan::new().run()? {
+    ///     orphan.disable()?;
+    /// }
+    /// ```
+    pub fn disable(&mut self) -> Result<()> {
+        self.entry.set_disabled(true)
+    }
+}
+
+/// Formats orphans as a human-readable report with one orphan per line.
+///
+/// # Examples
+///
+/// ```no_run
+/// let report = win_ctx::orphan_report(&OrphanScan::new().run()?);
+/// ```
+pub fn orphan_report(orphans: &[Orphan]) -> String {
+    orphans
+        .iter()
+        .map(|o| format!("{}: {:?} not found\n", o.address, o.executable))
+        .collect()
+}
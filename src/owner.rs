@@ -0,0 +1,159 @@
+use super::entry::hkcr;
+use super::error::{Error, Result, ResultExt};
+use super::notify;
+use super::*;
+use std::io;
+
+// Value storing the identifier of the tool that created an entry.
+pub(crate) const OWNER_VALUE: &str = "WinCtxOwner";
+
+impl CtxEntry {
+    /// Gets the identifier of the tool that created the entry, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", &ActivationType::Background)?;
+    /// let owner = entry.owner()?;
+    /// ```
+    pub fn owner(&self) -> Result<Option<String>> {
+        let key = self.key()?;
+        Ok(key.get_value::<String, _>(OWNER_VALUE).ok())
+    }
+
+    /// Sets the identifier of the tool that created the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::new("Basic entry", &ActivationType::Background)?;
+    /// entry.set_owner(Some("com.example.tool"))?;
+    /// ```
+    pub fn set_owner(&mut self, owner: Option<&str>) -> Result<()> {
+        match owner {
+            Some(owner) => self.write_value(OWNER_VALUE, &owner),
+            None => self.safe_delete_value(OWNER_VALUE),
+        }
+    }
+
+    /// Gets all entries with the given owner across all activation types.
+    /// Owned entries nested under other owned entries are not listed
+    /// separately. Entries that fail to read are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entries = CtxEntry::get_all_by_owner("com.example.tool")?;
+    /// ```
+    pub fn get_all_by_owner(owner: &str) -> Result<Vec<CtxEntry>> {
+        let mut entries = Vec::new();
+
+        for entry_type in ActivationType::all_registered()? {
+            let mut walk = CtxEntry::walk_type(&entry_type);
+
+            while let Some(item) = walk.next() {
+                let Ok(WalkEntry { entry, .. }) = item else {
+                    continue;
+                };
+
+                if entry.owner().ok().flatten().as_deref() == Some(owner) {
+                    walk.skip_children();
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Deletes all entries with the given owner and returns the paths of the
+    /// deleted keys. Fails without deleting anything if an owned entry has
+    /// children with a different or no owner, unless `force` is set.
+    ///
+    /// A parent left without children has its `Subcommands` value cleaned up
+    /// only if it has the same owner, or if `force` is set.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let deleted = CtxEntry::purge_owner("com.example.tool", false)?;
+    /// ```
+    pub fn purge_owner(owner: &str, force: bool) -> Result<Vec<String>> {
+        let entries = CtxEntry::get_all_by_owner(owner)?;
+
+        if !force {
+            for entry in &entries {
+                if let Some(path) = find_foreign(entry, owner)? {
+                    return Err(Error::permission_denied().with_context(
+                        "purging owner",
+                        &path,
+                        Some(OWNER_VALUE),
+                    ));
+                }
+            }
+        }
+
+        let _batch = notify::begin_batch();
+        let mut deleted = Vec::new();
+
+        for entry in entries {
+            let path = entry.path();
+            let parent = entry.parent()?;
+
+            hkcr()?
+                .delete_subkey_all(&path)
+                .context("deleting key", &path)?;
+            notify::changed();
+            deleted.push(path);
+
+            if let Some(parent) = parent
+                && (force || parent.owner()?.as_deref() == Some(owner))
+            {
+                parent.remove_empty_subcommands()?;
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    // Removes the `Subcommands` value and `shell` key from an entry with no
+    // children left, so it does not show up as an empty submenu. Entries
+    // whose children are stored under another key are left as is.
+    pub(crate) fn remove_empty_subcommands(&self) -> Result<()> {
+        if self.has_children()? || self.has_extended_subcommands()? {
+            return Ok(());
+        }
+
+        self.safe_delete_value("Subcommands")?;
+
+        match self.write_key()?.delete_subkey("shell") {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).context("deleting key", &format!("{}\\shell", self.path())),
+            Ok(_) => {
+                notify::changed();
+                Ok(())
+            }
+        }
+    }
+
+    // Checks whether the entry lists its children under another key with
+    // the `ExtendedSubCommandsKey` value.
+    pub(crate) fn has_extended_subcommands(&self) -> Result<bool> {
+        Ok(self.get_value("ExtendedSubCommandsKey")?.is_some())
+    }
+}
+
+// Finds the path of the first descendant not owned by the given owner.
+fn find_foreign(entry: &CtxEntry, owner: &str) -> Result<Option<String>> {
+    for child in entry.children()? {
+        if child.owner()?.as_deref() != Some(owner) {
+            return Ok(Some(child.path()));
+        }
+
+        if let Some(path) = find_foreign(&child, owner)? {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,101 @@
+use super::entry::{parse_position, parse_separator};
+use super::error::{Result, ResultExt};
+use super::owner::OWNER_VALUE;
+use super::*;
+use std::io;
+use winreg::types::FromRegValue;
+
+/// Known properties of an entry, read together by [`CtxEntry::properties`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryProperties {
+    /// Entry name
+    pub name: String,
+    /// Visible label (`MUIVerb`), if set
+    pub label: Option<String>,
+    /// Command to run when the entry is selected
+    pub command: Option<String>,
+    /// Icon to display beside the entry
+    pub icon: Option<String>,
+    /// Entry position in the context menu
+    pub position: Option<MenuPosition>,
+    /// Separators to include around the entry
+    pub separator: Option<Separator>,
+    /// Whether the entry should only appear with Shift+RClick
+    pub extended: bool,
+    /// Identifier of the tool that created the entry
+    pub owner: Option<String>,
+}
+
+impl EntryProperties {
+    /// Gets the visible label, which is the entry's name unless overridden.
+    pub fn display_label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl CtxEntry {
+    /// Reads all of the entry's known properties at once, listing its
+    /// values in a single pass instead of reading each one separately.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::get(&["Open directory in"], &ActivationType::Background)?.unwrap();
+    /// let props = entry.properties()?;
+    /// println!("{} runs {:?}", props.display_label(), props.command);
+    /// ```
+    pub fn properties(&self) -> Result<EntryProperties> {
+        self.read_properties().map(|(props, _)| props)
+    }
+
+    // Reads the known properties along with every raw value on the key.
+    pub(crate) fn read_properties(
+        &self,
+    ) -> Result<(EntryProperties, Vec<(String, winreg::RegValue)>)> {
+        let key = self.key()?;
+        let path = self.path();
+
+        let raw_values = key
+            .enum_values()
+            .collect::<io::Result<Vec<_>>>()
+            .context("listing values", &path)?;
+
+        let mut props = EntryProperties {
+            name: self.name_path.last().unwrap().to_owned(),
+            label: None,
+            command: None,
+            icon: None,
+            position: None,
+            separator: None,
+            extended: false,
+            owner: None,
+        };
+        let mut sep_before = false;
+        let mut sep_after = false;
+
+        for (name, raw) in &raw_values {
+            // Flags are recognized by presence alone, as with the getters.
+            let value = String::from_reg_value(raw).ok();
+
+            match name.to_lowercase().as_str() {
+                "muiverb" => props.label = value,
+                "icon" => props.icon = value,
+                "position" => props.position = value.as_deref().and_then(parse_position),
+                "separatorbefore" => sep_before = true,
+                "separatorafter" => sep_after = true,
+                "extended" => props.extended = true,
+                n if n.eq_ignore_ascii_case(OWNER_VALUE) => props.owner = value,
+                _ => {}
+            }
+        }
+
+        props.separator = parse_separator(sep_before, sep_after);
+        props.command = match key.open_subkey("command") {
+            Ok(command_key) => command_key.get_value::<String, _>("").ok(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).context("opening key", &format!("{path}\\command")),
+        };
+
+        Ok((props, raw_values))
+    }
+}
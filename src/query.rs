@@ -0,0 +1,284 @@
+use super::error::Result;
+use super::*;
+
+/// Filter for finding entries across activation types, including nested
+/// children. All set conditions must match.
+///
+/// # Examples
+///
+/// ```no_run
+/// let matches = Query::new().command("code.exe").run()?;
+///
+/// for m in matches {
+///     println!("{}", m.address);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    entry_types: Option<Vec<ActivationType>>,
+    name: Option<String>,
+    label: Option<String>,
+    command: Option<String>,
+    #[cfg(feature = "regex")]
+    command_regex: Option<regex::Regex>,
+    icon: Option<String>,
+    extended: Option<bool>,
+    position: Option<MenuPosition>,
+    separator: Option<Separator>,
+    has_children: Option<bool>,
+    min_depth: usize,
+    max_depth: Option<usize>,
+    owner: Option<String>,
+}
+
+/// Entry matched by a query
+#[derive(Debug)]
+pub struct QueryMatch {
+    pub entry: CtxEntry,
+    pub address: EntryAddress,
+    /// Nesting depth of the entry, starting at 0 for root entries
+    pub depth: usize,
+}
+
+impl Query {
+    /// Creates a query matching every entry.
+    pub fn new() -> Query {
+        Query::default()
+    }
+
+    /// Limits the query to the given activation type. Can be called multiple
+    /// times. By default, all registered activation types are searched.
+    pub fn entry_type(mut self, entry_type: &ActivationType) -> Query {
+        self.entry_types
+            .get_or_insert_with(Vec::new)
+            .push(entry_type.clone());
+        self
+    }
+
+    /// Matches entry key names against a case-insensitive pattern, where `*`
+    /// matches any characters and `?` matches a single character.
+    pub fn name(mut self, pattern: &str) -> Query {
+        self.name = Some(pattern.to_string());
+        self
+    }
+
+    /// Matches entry labels against a case-insensitive pattern, where `*`
+    /// matches any characters and `?` matches a single character.
+    pub fn label(mut self, pattern: &str) -> Query {
+        self.label = Some(pattern.to_string());
+        self
+    }
+
+    /// Matches entries whose command contains the given text, ignoring case.
+    pub fn command(mut self, text: &str) -> Query {
+        self.command = Some(text.to_string());
+        self
+    }
+
+    /// Matches entries whose command matches the given regular expression.
+    #[cfg(feature = "regex")]
+    pub fn command_regex(mut self, regex: regex::Regex) -> Query {
+        self.command_regex = Some(regex);
+        self
+    }
+
+    /// Matches entries whose icon contains the given text, ignoring case.
+    pub fn icon(mut self, text: &str) -> Query {
+        self.icon = Some(text.to_string());
+        self
+    }
+
+    /// Matches entries that do or do not only appear with Shift+RClick.
+    pub fn extended(mut self, extended: bool) -> Query {
+        self.extended = Some(extended);
+        self
+    }
+
+    /// Matches entries with the given menu position.
+    pub fn position(mut self, position: MenuPosition) -> Query {
+        self.position = Some(position);
+        self
+    }
+
+    /// Matches entries with the given separator(s).
+    pub fn separator(mut self, separator: Separator) -> Query {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// Matches entries that do or do not have children.
+    pub fn has_children(mut self, has_children: bool) -> Query {
+        self.has_children = Some(has_children);
+        self
+    }
+
+    /// Skips entries nested less deeply than the given depth, where root
+    /// entries have a depth of 0.
+    pub fn min_depth(mut self, depth: usize) -> Query {
+        self.min_depth = depth;
+        self
+    }
+
+    /// Skips entries nested more deeply than the given depth, where root
+    /// entries have a depth of 0.
+    pub fn max_depth(mut self, depth: usize) -> Query {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Matches entries with the given owner.
+    pub fn owner(mut self, owner: &str) -> Query {
+        self.owner = Some(owner.to_string());
+        self
+    }
+
+    /// Runs the query. Results are ordered by activation type, then
+    /// depth-first in menu order. Fails if an entry can't be read.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let shift_only = Query::new().extended(true).run()?;
+    /// ```
+    pub fn run(&self) -> Result<Vec<QueryMatch>> {
+        let entry_types = match &self.entry_types {
+            Some(entry_types) => entry_types.clone(),
+            None => ActivationType::all_registered()?,
+        };
+
+        let mut matches = Vec::new();
+
+        for entry_type in entry_types {
+            let mut walk = CtxEntry::walk_type(&entry_type);
+
+            if let Some(max) = self.max_depth {
+                walk = walk.max_depth(max);
+            }
+
+            for item in walk {
+                let WalkEntry { entry, depth } = item?;
+
+                if depth >= self.min_depth && self.matches(&entry)? {
+                    matches.push(QueryMatch {
+                        address: entry.address(),
+                        entry,
+                        depth,
+                    });
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+
+    fn matches(&self, entry: &CtxEntry) -> Result<bool> {
+        if self.has_children.is_some() && self.has_children != Some(entry.has_children()?) {
+            return Ok(false);
+        }
+
+        let props = entry.properties()?;
+
+        if let Some(pattern) = &self.name
+            && !glob_match(pattern, &props.name)
+        {
+            return Ok(false);
+        }
+        if let Some(pattern) = &self.label
+            && !glob_match(pattern, props.display_label())
+        {
+            return Ok(false);
+        }
+        if let Some(text) = &self.command
+            && !props.command.as_ref().is_some_and(|c| contains(c, text))
+        {
+            return Ok(false);
+        }
+        #[cfg(feature = "regex")]
+        if let Some(regex) = &self.command_regex
+            && !props.command.as_ref().is_some_and(|c| regex.is_match(c))
+        {
+            return Ok(false);
+        }
+        if let Some(text) = &self.icon
+            && !props.icon.as_ref().is_some_and(|i| contains(i, text))
+        {
+            return Ok(false);
+        }
+        if self.extended.is_some() && self.extended != Some(props.extended) {
+            return Ok(false);
+        }
+        if self.position.is_some() && self.position != props.position {
+            return Ok(false);
+        }
+        if self.separator.is_some() && self.separator != props.separator {
+            return Ok(false);
+        }
+        if let Some(owner) = &self.owner
+            && props.owner.as_deref() != Some(owner.as_str())
+        {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+// Matches a case-insensitive pattern with `*` and `?` wildcards.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at.
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bt)) => {
+                    p = bp;
+                    t = bt + 1;
+                    backtrack = Some((bp, bt + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_literal() {
+        assert!(glob_match("Open in terminal", "open in Terminal"));
+        assert!(!glob_match("Open in", "Open in terminal"));
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob_match("Open*", "Open in terminal"));
+        assert!(glob_match("*terminal", "Open in terminal"));
+        assert!(glob_match("*in*", "Open in terminal"));
+        assert!(glob_match("Op?n*", "Open in terminal"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(!glob_match("?", ""));
+    }
+}
@@ -0,0 +1,300 @@
+use super::error::Result;
+use super::walk::read_child_names;
+use super::*;
+use std::fmt;
+
+/// Options for rendering entries as a text tree
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    /// Maximum line length in characters. Longer lines are cut off with an
+    /// ellipsis.
+    pub width: Option<usize>,
+    /// Whether to draw the tree with ASCII characters only. Labels and
+    /// commands are shown as is.
+    pub ascii: bool,
+    /// Fields to leave out of each line. The label is always shown.
+    pub hidden: Vec<TreeField>,
+}
+
+/// Field shown on each line of a rendered tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeField {
+    /// Key name, shown when it differs from the label
+    Name,
+    Command,
+    Icon,
+    Position,
+    Separator,
+    /// Whether the entry only appears with Shift+RClick or is disabled
+    Flags,
+}
+
+// Characters used to draw a tree.
+struct Glyphs {
+    branch: &'static str,
+    last: &'static str,
+    pipe: &'static str,
+    space: &'static str,
+    arrow: &'static str,
+    ellipsis: &'static str,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    branch: "├─ ",
+    last: "└─ ",
+    pipe: "│  ",
+    space: "   ",
+    arrow: "→",
+    ellipsis: "…",
+};
+
+const ASCII: Glyphs = Glyphs {
+    branch: "|- ",
+    last: "`- ",
+    pipe: "|  ",
+    space: "   ",
+    arrow: "->",
+    ellipsis: "...",
+};
+
+impl EntrySnapshot {
+    /// Renders the entry and its children as an indented tree with one
+    /// entry per line, such as `├─ Terminal [shift] → cmd.exe`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let tree = entry.snapshot()?.render(&TreeOptions {
+    ///     ascii: true,
+    ///     hidden: vec![TreeField::Icon],
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn render(&self, opts: &TreeOptions) -> String {
+        let mut out = String::new();
+        push_line(&mut out, entry_line(self, opts), opts);
+        render_children(&self.children, "", opts, &mut out);
+        out
+    }
+}
+
+impl CtxEntry {
+    /// Renders the entry and its children as an indented tree with one
+    /// entry per line.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::get(&["Open directory in"], &ActivationType::Background)?.unwrap();
+    /// print!("{}", entry.render(&TreeOptions::default())?);
+    /// ```
+    pub fn render(&self, opts: &TreeOptions) -> Result<String> {
+        Ok(self.snapshot()?.render(opts))
+    }
+
+    /// Renders every entry of the given type as an indented tree under a
+    /// line naming the type (e.g., `folder`). Entries whose names fail to
+    /// read are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// print!("{}", CtxEntry::render_type(&ActivationType::Folder, &TreeOptions::default())?);
+    /// ```
+    pub fn render_type(entry_type: &ActivationType, opts: &TreeOptions) -> Result<String> {
+        let mut snapshots = Vec::new();
+
+        for name in read_child_names(entry_type, &[])?.into_iter().flatten() {
+            let entry = CtxEntry {
+                name_path: vec![name],
+                entry_type: entry_type.clone(),
+                handle: None,
+            };
+            snapshots.push(entry.snapshot()?);
+        }
+
+        let mut out = String::new();
+        push_line(&mut out, entry_type.to_string(), opts);
+        render_children(&snapshots, "", opts, &mut out);
+        Ok(out)
+    }
+}
+
+fn render_children(children: &[EntrySnapshot], prefix: &str, opts: &TreeOptions, out: &mut String) {
+    let glyphs = glyphs(opts);
+
+    for (i, child) in children.iter().enumerate() {
+        let (branch, indent) = if i + 1 == children.len() {
+            (glyphs.last, glyphs.space)
+        } else {
+            (glyphs.branch, glyphs.pipe)
+        };
+
+        push_line(
+            out,
+            format!("{prefix}{branch}{}", entry_line(child, opts)),
+            opts,
+        );
+        render_children(&child.children, &format!("{prefix}{indent}"), opts, out);
+    }
+}
+
+// Formats an entry as `label (name) [flags] → command (icon: icon)`.
+fn entry_line(snapshot: &EntrySnapshot, opts: &TreeOptions) -> String {
+    let shown = |field| !opts.hidden.contains(&field);
+    let label = snapshot.label.as_deref().unwrap_or(&snapshot.name);
+    let mut line = label.to_string();
+
+    if shown(TreeField::Name) && label != snapshot.name {
+        line.push_str(&format!(" ({})", snapshot.name));
+    }
+
+    let mut tags = Vec::new();
+
+    if shown(TreeField::Flags) {
+        if snapshot.extended {
+            tags.push("shift");
+        }
+        if snapshot
+            .values
+            .keys()
+            .any(|v| v.eq_ignore_ascii_case("LegacyDisable"))
+        {
+            tags.push("disabled");
+        }
+    }
+    if shown(TreeField::Position) {
+        match snapshot.position {
+            Some(MenuPosition::Top) => tags.push("top"),
+            Some(MenuPosition::Bottom) => tags.push("bottom"),
+            None => {}
+        }
+    }
+    if shown(TreeField::Separator) {
+        match snapshot.separator {
+            Some(Separator::Before) => tags.push("separator before"),
+            Some(Separator::After) => tags.push("separator after"),
+            Some(Separator::Both) => tags.push("separators"),
+            None => {}
+        }
+    }
+
+    if !tags.is_empty() {
+        line.push_str(&format!(" [{}]", tags.join(", ")));
+    }
+    if shown(TreeField::Command)
+        && let Some(command) = &snapshot.command
+    {
+        line.push_str(&format!(" {} {command}", glyphs(opts).arrow));
+    }
+    if shown(TreeField::Icon)
+        && let Some(icon) = &snapshot.icon
+    {
+        line.push_str(&format!(" (icon: {icon})"));
+    }
+
+    line
+}
+
+fn push_line(out: &mut String, line: String, opts: &TreeOptions) {
+    let ellipsis = glyphs(opts).ellipsis;
+
+    match opts.width {
+        Some(width) if line.chars().count() > width => {
+            let keep = width.saturating_sub(ellipsis.chars().count());
+            out.extend(line.chars().take(keep));
+            out.extend(ellipsis.chars().take(width - keep));
+        }
+        _ => out.push_str(&line),
+    }
+
+    out.push('\n');
+}
+
+fn glyphs(opts: &TreeOptions) -> &'static Glyphs {
+    if opts.ascii { &ASCII } else { &UNICODE }
+}
+
+impl fmt::Display for EntrySnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tree = self.render(&TreeOptions::default());
+        f.write_str(tree.trim_end_matches('\n'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu() -> EntrySnapshot {
+        let mut terminal = EntrySnapshot::tree("01_Terminal", vec![]);
+        terminal.label = Some("Terminal".to_string());
+        terminal.extended = true;
+        terminal.command = Some(r#"cmd /s /k pushd "%V""#.to_string());
+        terminal.icon = Some("cmd.exe".to_string());
+
+        let mut admin = EntrySnapshot::tree("Admin", vec![]);
+        admin.separator = Some(Separator::Before);
+        admin
+            .values
+            .insert("LegacyDisable".to_string(), RegValue::Sz(String::new()));
+
+        let mut parent = EntrySnapshot::tree(
+            "Open in",
+            vec![terminal, EntrySnapshot::tree("Nested", vec![admin])],
+        );
+        parent.position = Some(MenuPosition::Top);
+        parent
+    }
+
+    #[test]
+    fn render_unicode() {
+        assert_eq!(
+            menu().to_string(),
+            "Open in [top]\n\
+             ├─ Terminal (01_Terminal) [shift] → cmd /s /k pushd \"%V\" (icon: cmd.exe)\n\
+             └─ Nested\n   \
+                └─ Admin [disabled, separator before]"
+        );
+    }
+
+    #[test]
+    fn render_ascii_with_hidden_fields() {
+        let opts = TreeOptions {
+            ascii: true,
+            hidden: vec![TreeField::Name, TreeField::Icon, TreeField::Flags],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            menu().render(&opts),
+            "Open in [top]\n\
+             |- Terminal -> cmd /s /k pushd \"%V\"\n\
+             `- Nested\n   \
+                `- Admin [separator before]\n"
+        );
+    }
+
+    #[test]
+    fn render_width() {
+        let opts = TreeOptions {
+            width: Some(12),
+            ..Default::default()
+        };
+        let lines: Vec<String> = menu().render(&opts).lines().map(String::from).collect();
+
+        assert_eq!(
+            lines[0],
+            "Open in [top]".chars().take(11).collect::<String>() + "…"
+        );
+        assert_eq!(lines[2], "└─ Nested");
+        assert!(lines.iter().all(|l| l.chars().count() <= 12));
+
+        let opts = TreeOptions {
+            width: Some(2),
+            ascii: true,
+            ..Default::default()
+        };
+        assert!(menu().render(&opts).lines().all(|l| l == ".."));
+    }
+}
@@ -0,0 +1,262 @@
+use super::entry::hkcr;
+use super::error::{Result, ResultExt};
+use super::notify;
+use super::walk::read_child_names;
+use super::*;
+use std::fmt;
+use std::io;
+
+/// Inconsistency found by [`CtxEntry::repair`] and how it is fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairKind {
+    /// Entry with children lacks the `Subcommands` value, so its children
+    /// are hidden. The value is added.
+    MissingSubcommands,
+    /// Entry has a `command` key without a command. The key is removed.
+    EmptyCommand,
+    /// Entry without children still has the `Subcommands` value, so it
+    /// shows up as an empty submenu. The value is removed. Entries whose
+    /// children are stored under `ExtendedSubCommandsKey` are not affected.
+    StaleSubcommands,
+    /// Entry with a command but no `Subcommands` value has children, which
+    /// are never shown. Left as is unless
+    /// [`RepairOptions::show_hidden_children`] is set, which adds the value
+    /// so the children are shown instead of the command.
+    UnreachableChildren,
+}
+
+/// Fix applied, or to be applied in a dry run, by [`CtxEntry::repair`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub kind: RepairKind,
+    /// Address of the repaired entry
+    pub address: EntryAddress,
+    /// Whether the problem is left as is and needs manual action
+    pub manual: bool,
+}
+
+/// Options for repairing entries
+#[derive(Debug, Clone, Default)]
+pub struct RepairOptions {
+    /// Whether to only return the fixes without applying them
+    pub dry_run: bool,
+    /// Whether to show children hidden by their parent's command by adding
+    /// the `Subcommands` value, which hides the command instead. Nothing is
+    /// deleted either way.
+    pub show_hidden_children: bool,
+}
+
+impl CtxEntry {
+    /// Finds and fixes inconsistencies in the entry and its children, and
+    /// returns the fixes. With `dry_run` set, nothing is changed and the
+    /// fixes that would be applied are returned. Problems that cannot be
+    /// fixed safely are returned as needing manual action.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::get(&["Open directory in"], &ActivationType::Background)?.unwrap();
+    /// let planned = entry.repair(true)?;
+    /// print!("{}", win_ctx::repair_report(&planned));
+    /// entry.repair(false)?;
+    /// ```
+    pub fn repair(&self, dry_run: bool) -> Result<Vec<Repair>> {
+        self.repair_with_options(&RepairOptions {
+            dry_run,
+            ..Default::default()
+        })
+    }
+
+    /// Finds and fixes inconsistencies in the entry and its children, and
+    /// returns the fixes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let repairs = entry.repair_with_options(&RepairOptions {
+    ///     show_hidden_children: true,
+    ///     ..Default::default()
+    /// })?;
+    /// ```
+    pub fn repair_with_options(&self, opts: &RepairOptions) -> Result<Vec<Repair>> {
+        let _batch = notify::begin_batch();
+        let mut repairs = Vec::new();
+        repair_entry(self, opts, &mut repairs)?;
+        Ok(repairs)
+    }
+
+    /// Finds and fixes inconsistencies in every entry of the given type, and
+    /// returns the fixes. With `dry_run` set, nothing is changed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let repairs = CtxEntry::repair_type(&ActivationType::Folder, false)?;
+    /// ```
+    pub fn repair_type(entry_type: &ActivationType, dry_run: bool) -> Result<Vec<Repair>> {
+        CtxEntry::repair_type_with_options(
+            entry_type,
+            &RepairOptions {
+                dry_run,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Finds and fixes inconsistencies in every entry of the given type, and
+    /// returns the fixes. Entries whose names fail to read are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let opts = RepairOptions { dry_run: true, ..Default::default() };
+    /// let repairs = CtxEntry::repair_type_with_options(&ActivationType::Folder, &opts)?;
+    /// ```
+    pub fn repair_type_with_options(
+        entry_type: &ActivationType,
+        opts: &RepairOptions,
+    ) -> Result<Vec<Repair>> {
+        let _batch = notify::begin_batch();
+        let mut repairs = Vec::new();
+
+        for name in read_child_names(entry_type, &[])?.into_iter().flatten() {
+            let entry = CtxEntry {
+                name_path: vec![name],
+                entry_type: entry_type.clone(),
+                handle: None,
+            };
+            repair_entry(&entry, opts, &mut repairs)?;
+        }
+
+        Ok(repairs)
+    }
+}
+
+/// Formats repairs as a human-readable report with one repair per line.
+///
+/// # Examples
+///
+/// ```no_run
+/// let report = win_ctx::repair_report(&entry.repair(true)?);
+/// ```
+pub fn repair_report(repairs: &[Repair]) -> String {
+    repairs.iter().map(|r| format!("{r}\n")).collect()
+}
+
+fn repair_entry(entry: &CtxEntry, opts: &RepairOptions, repairs: &mut Vec<Repair>) -> Result<()> {
+    let dry_run = opts.dry_run;
+    let mut report = |kind, manual| {
+        repairs.push(Repair {
+            kind,
+            address: entry.address(),
+            manual,
+        })
+    };
+
+    let mut has_command = has_command_key(entry)?;
+
+    if has_command && is_empty_command(entry)? {
+        report(RepairKind::EmptyCommand, false);
+        has_command = false;
+
+        if !dry_run {
+            let path = format!("{}\\command", entry.path());
+            hkcr()?
+                .delete_subkey(&path)
+                .context("deleting key", &path)?;
+            notify::changed();
+        }
+    }
+
+    let subcommands = entry.get_value("Subcommands")?;
+
+    if !entry.has_children()? {
+        // A non-empty value lists verbs from the command store instead.
+        if subcommands
+            .as_ref()
+            .is_some_and(|v| v.as_str().is_none_or(str::is_empty))
+            && !entry.has_extended_subcommands()?
+        {
+            report(RepairKind::StaleSubcommands, false);
+
+            if !dry_run {
+                entry.remove_empty_subcommands()?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    if subcommands.is_none() {
+        let (kind, fix) = if has_command {
+            // Adding the value hides the command, so only do it on request.
+            (RepairKind::UnreachableChildren, opts.show_hidden_children)
+        } else {
+            (RepairKind::MissingSubcommands, true)
+        };
+
+        report(kind, !fix);
+
+        if fix && !dry_run {
+            entry.write_value("Subcommands", &"")?;
+        }
+    }
+
+    for child in &entry.children()? {
+        repair_entry(child, opts, repairs)?;
+    }
+
+    Ok(())
+}
+
+fn has_command_key(entry: &CtxEntry) -> Result<bool> {
+    match entry.key()?.open_subkey("command") {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e).context("opening key", &format!("{}\\command", entry.path())),
+    }
+}
+
+// Checks whether the `command` key has no command. Keys with other values
+// (e.g., `DelegateExecute`) or subkeys are not empty.
+fn is_empty_command(entry: &CtxEntry) -> Result<bool> {
+    let path = format!("{}\\command", entry.path());
+    let key = entry
+        .key()?
+        .open_subkey("command")
+        .context("opening key", &path)?;
+
+    if key.enum_keys().next().is_some() {
+        return Ok(false);
+    }
+
+    for value in key.enum_values() {
+        let (name, value) = value.context("listing values", &path)?;
+
+        if !name.is_empty()
+            || !RegValue::from_raw(&value)
+                .as_str()
+                .is_some_and(str::is_empty)
+        {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fix = match self.kind {
+            RepairKind::MissingSubcommands => "add missing `Subcommands` value",
+            RepairKind::EmptyCommand => "remove empty `command` key",
+            RepairKind::StaleSubcommands => "remove `Subcommands` value without children",
+            RepairKind::UnreachableChildren if self.manual => {
+                "children hidden by the command (needs manual action)"
+            }
+            RepairKind::UnreachableChildren => "add `Subcommands` value to show hidden children",
+        };
+
+        write!(f, "{}: {fix}", self.address)
+    }
+}
@@ -0,0 +1,253 @@
+use super::entry::{hkcr, open_key, read_key};
+use super::error::{Error, Result, ResultExt};
+use super::notify;
+use super::path::get_full_path;
+use super::*;
+use std::collections::BTreeMap;
+use winreg::RegKey;
+
+/// Owned copy of an entry and its children at a point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntrySnapshot {
+    /// Entry name
+    pub name: String,
+    /// Visible label (`MUIVerb`), if set
+    pub label: Option<String>,
+    /// Command to run when the entry is selected
+    pub command: Option<String>,
+    /// Icon to display beside the entry
+    pub icon: Option<String>,
+    /// Entry position in the context menu
+    pub position: Option<MenuPosition>,
+    /// Separators to include around the entry
+    pub separator: Option<Separator>,
+    /// Whether the entry should only appear with Shift+RClick
+    pub extended: bool,
+    /// Registry values on the entry's key as stored, including those
+    /// represented by other fields (e.g., `Icon`)
+    pub values: BTreeMap<String, RegValue>,
+    /// Subkeys other than `shell` as stored, including `command`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub keys: BTreeMap<String, KeySnapshot>,
+    /// Child entries in menu order
+    pub children: Vec<EntrySnapshot>,
+}
+
+#[cfg(test)]
+impl EntrySnapshot {
+    // Snapshot with only a name and children, for building trees in tests.
+    pub(crate) fn tree(name: &str, children: Vec<EntrySnapshot>) -> EntrySnapshot {
+        EntrySnapshot {
+            name: name.to_string(),
+            children,
+            ..Default::default()
+        }
+    }
+}
+
+/// Owned copy of a registry key that is not an entry, such as `command`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeySnapshot {
+    /// Registry values, where `""` is the key's default value
+    pub values: BTreeMap<String, RegValue>,
+    /// Subkeys by name
+    pub keys: BTreeMap<String, KeySnapshot>,
+}
+
+impl CtxEntry {
+    /// Takes a snapshot of the entry and all of its children.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::get(&["Open directory in"], &ActivationType::Background).unwrap();
+    /// let snapshot = entry.snapshot()?;
+    /// ```
+    pub fn snapshot(&self) -> Result<EntrySnapshot> {
+        let (props, raw_values) = self.read_properties()?;
+        let values = raw_values
+            .iter()
+            .map(|(name, raw)| (name.clone(), RegValue::from_raw(raw)))
+            .collect();
+
+        let path = self.path();
+        let key = self.key()?;
+        let mut keys = BTreeMap::new();
+
+        for name in key.enum_keys() {
+            let name = name.context("listing keys", &path)?;
+
+            if !name.eq_ignore_ascii_case("shell") {
+                let sub_path = format!("{path}\\{name}");
+                let subkey = key.open_subkey(&name).context("opening key", &sub_path)?;
+                keys.insert(name, KeySnapshot::read(&subkey, &sub_path)?);
+            }
+        }
+
+        let mut children = Vec::new();
+
+        for child in self.children()? {
+            children.push(child.snapshot()?);
+        }
+
+        Ok(EntrySnapshot {
+            name: props.name,
+            label: props.label,
+            command: props.command,
+            icon: props.icon,
+            position: props.position,
+            separator: props.separator,
+            extended: props.extended,
+            values,
+            keys,
+            children,
+        })
+    }
+}
+
+impl KeySnapshot {
+    fn read(key: &RegKey, path: &str) -> Result<KeySnapshot> {
+        let mut snapshot = KeySnapshot::default();
+
+        for value in key.enum_values() {
+            let (name, raw) = value.context("listing values", path)?;
+            snapshot.values.insert(name, RegValue::from_raw(&raw));
+        }
+
+        for name in key.enum_keys() {
+            let name = name.context("listing keys", path)?;
+            let sub_path = format!("{path}\\{name}");
+            let subkey = key.open_subkey(&name).context("opening key", &sub_path)?;
+            snapshot
+                .keys
+                .insert(name, KeySnapshot::read(&subkey, &sub_path)?);
+        }
+
+        Ok(snapshot)
+    }
+
+    fn write(&self, key: &RegKey, path: &str) -> Result<()> {
+        write_values(key, path, &self.values)?;
+        write_keys(key, path, &self.keys)
+    }
+}
+
+// Fails if an entry already exists at the given name path, and returns
+// the path of its key otherwise.
+fn check_free(name_path: &[String], entry_type: &ActivationType) -> Result<String> {
+    let path = get_full_path(entry_type, name_path);
+
+    if read_key(&path).is_ok() {
+        return Err(Error::already_exists().with_context("restoring entry", &path, None));
+    }
+
+    Ok(path)
+}
+
+fn write_values(key: &RegKey, path: &str, values: &BTreeMap<String, RegValue>) -> Result<()> {
+    for (name, value) in values {
+        key.set_raw_value(name, &value.to_raw())
+            .value_context("writing value", path, name)?;
+        notify::changed();
+    }
+
+    Ok(())
+}
+
+fn write_keys(key: &RegKey, path: &str, keys: &BTreeMap<String, KeySnapshot>) -> Result<()> {
+    for (name, snapshot) in keys {
+        let sub_path = format!("{path}\\{name}");
+        let (subkey, _) = key.create_subkey(name).context("creating key", &sub_path)?;
+        snapshot.write(&subkey, &sub_path)?;
+    }
+
+    Ok(())
+}
+
+impl EntrySnapshot {
+    /// Recreates the snapshot as a new top-level entry with the given
+    /// entry type.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let snapshot = entry.snapshot()?;
+    /// entry.delete()?;
+    /// let restored = snapshot.restore(&ActivationType::Background)?;
+    /// ```
+    pub fn restore(&self, entry_type: &ActivationType) -> Result<CtxEntry> {
+        self.restore_at(vec![self.name.clone()], entry_type)
+    }
+
+    /// Recreates the snapshot as a new child of the given entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let parent = CtxEntry::new("Tools", &ActivationType::Folder)?;
+    /// let restored = snapshot.restore_as_child(&parent)?;
+    /// ```
+    pub fn restore_as_child(&self, parent: &CtxEntry) -> Result<CtxEntry> {
+        let _batch = notify::begin_batch();
+        let mut name_path = parent.name_path.clone();
+        name_path.push(self.name.clone());
+
+        let restored = self.restore_at(name_path, &parent.entry_type)?;
+        parent.write_value("Subcommands", &"")?;
+        Ok(restored)
+    }
+
+    // Writes the snapshot, removing anything partially written on failure.
+    fn restore_at(&self, name_path: Vec<String>, entry_type: &ActivationType) -> Result<CtxEntry> {
+        let _batch = notify::begin_batch();
+        let path = check_free(&name_path, entry_type)?;
+
+        self.write(&name_path, entry_type).inspect_err(|_| {
+            if let Ok(root) = hkcr() {
+                let _ = root.delete_subkey_all(&path);
+            }
+        })
+    }
+
+    // Writes the stored values and keys as is, then applies any fields
+    // that disagree with them, so edited snapshots restore as edited.
+    fn write(&self, name_path: &[String], entry_type: &ActivationType) -> Result<CtxEntry> {
+        let mut entry = CtxEntry::create(name_path, entry_type, &EntryOptions::default())?;
+        let path = entry.path();
+        let key = open_key(&path, Access::ReadWrite)?;
+
+        write_values(&key, &path, &self.values)?;
+        write_keys(&key, &path, &self.keys)?;
+
+        let props = entry.properties()?;
+
+        if props.label != self.label {
+            entry.set_label(self.label.as_deref())?;
+        }
+        if props.command != self.command {
+            entry.set_command(self.command.as_deref())?;
+        }
+        if props.icon != self.icon {
+            entry.set_icon(self.icon.as_deref())?;
+        }
+        if props.position != self.position {
+            entry.set_position(self.position.clone())?;
+        }
+        if props.separator != self.separator {
+            entry.set_separator(self.separator.clone())?;
+        }
+        if props.extended != self.extended {
+            entry.set_extended(self.extended)?;
+        }
+
+        for child in &self.children {
+            let mut child_path = name_path.to_vec();
+            child_path.push(child.name.clone());
+            child.write(&child_path, entry_type)?;
+        }
+
+        Ok(entry)
+    }
+}
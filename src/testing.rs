@@ -0,0 +1,389 @@
+//! Sandboxed registry roots, stock Windows fixtures and assertions for
+//! testing code that manages entries. Requires the `testing` feature.
+
+use super::error::{Result, ResultExt};
+use super::*;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use winreg::RegKey;
+use winreg::enums::*;
+
+// Key under `HKEY_CURRENT_USER` holding sandbox roots. It is outside
+// `Software\Classes`, so nothing in it shows up in real context menus.
+const SANDBOX_PATH: &str = r"Software\win-ctx\sandbox";
+
+static NEXT_SANDBOX: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Paths of the sandboxes active on the thread, innermost last.
+    static SANDBOXES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Empty registry root that replaces `HKEY_CLASSES_ROOT` for every entry
+/// operation, and both hives of the classic menu setting, on the current
+/// thread until dropped, so tests never touch real context menus. Sandboxes on different threads are independent,
+/// and a sandbox created while another is active replaces it until dropped.
+/// [`ShellNotifier`] does nothing while a sandbox is active.
+/// The sandbox key is deleted on drop.
+///
+/// # Examples
+///
+/// ```no_run
+/// use win_ctx::testing::{Fixture, Sandbox};
+///
+/// let sandbox = Sandbox::with_fixture(Fixture::Windows11)?;
+/// let entries = CtxEntry::get_all_of_type(&ActivationType::Background);
+/// assert_eq!(entries.len(), Fixture::Windows11.entry_names(&ActivationType::Background).len());
+/// ```
+#[derive(Debug)]
+pub struct Sandbox {
+    path: String,
+    // The sandbox only applies to the thread that created it.
+    _thread: PhantomData<*const ()>,
+}
+
+/// Typical shell keys of a stock Windows installation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixture {
+    Windows10,
+    Windows11,
+}
+
+// Value written by a fixture.
+enum Value {
+    Sz(&'static str),
+    ExpandSz(&'static str),
+    Dword(u32),
+}
+
+use Value::*;
+
+type Values = &'static [(&'static str, Value)];
+
+// Keys as paths with their values, where an empty value name is the key's
+// default value.
+type FixtureKeys = &'static [(&'static str, Values)];
+
+const VELOCITY_ID: u32 = 0x639bc8;
+const ENCRYPTION_HANDLER: &str = "{1d27f844-3a1f-4410-85ac-14651078412d}";
+const PIN_HANDLER: &str = "{b455f46e-e4af-4035-b0a4-cf18d2f6f28e}";
+
+const CMD: Values = &[
+    ("MUIVerb", Sz("@shell32.dll,-8506")),
+    ("Extended", Sz("")),
+    ("NoWorkingDirectory", Sz("")),
+    ("HideBasedOnVelocityId", Dword(VELOCITY_ID)),
+];
+const CMD_COMMAND: Values = &[("", Sz(r#"cmd.exe /s /k pushd "%V""#))];
+
+const POWERSHELL: Values = &[
+    ("MUIVerb", Sz("@shell32.dll,-8508")),
+    ("Extended", Sz("")),
+    ("NoWorkingDirectory", Sz("")),
+    ("ShowBasedOnVelocityId", Dword(VELOCITY_ID)),
+];
+const POWERSHELL_COMMAND: Values = &[(
+    "",
+    Sz("powershell.exe -noexit -command Set-Location -literalPath '%V'"),
+)];
+
+const ENCRYPTION: Values = &[
+    (
+        "MUIVerb",
+        ExpandSz(r"@%SystemRoot%\system32\efswrt.dll,-108"),
+    ),
+    ("ProgrammaticAccessOnly", Sz("")),
+];
+const ENCRYPTION_COMMAND: Values = &[("DelegateExecute", Sz(ENCRYPTION_HANDLER))];
+
+const WINDOWS_10: FixtureKeys = &[
+    (r"*\shell\UpdateEncryptionSettingsWork", ENCRYPTION),
+    (
+        r"*\shell\UpdateEncryptionSettingsWork\command",
+        ENCRYPTION_COMMAND,
+    ),
+    (r"Directory\shell\cmd", CMD),
+    (r"Directory\shell\cmd\command", CMD_COMMAND),
+    (
+        r"Directory\shell\find",
+        &[
+            ("SuppressionPolicy", Dword(0x80)),
+            ("LegacyDisable", Sz("")),
+        ],
+    ),
+    (
+        r"Directory\shell\find\command",
+        &[("", ExpandSz(r"%SystemRoot%\Explorer.exe"))],
+    ),
+    (r"Directory\shell\Powershell", POWERSHELL),
+    (r"Directory\shell\Powershell\command", POWERSHELL_COMMAND),
+    (r"Directory\shell\UpdateEncryptionSettings", ENCRYPTION),
+    (
+        r"Directory\shell\UpdateEncryptionSettings\command",
+        ENCRYPTION_COMMAND,
+    ),
+    (r"Directory\Background\shell\cmd", CMD),
+    (r"Directory\Background\shell\cmd\command", CMD_COMMAND),
+    (r"Directory\Background\shell\Powershell", POWERSHELL),
+    (
+        r"Directory\Background\shell\Powershell\command",
+        POWERSHELL_COMMAND,
+    ),
+];
+
+// Keys added by Windows 11 on top of the Windows 10 ones.
+const WINDOWS_11: FixtureKeys = &[
+    (
+        r"*\shell\pintohomefile",
+        &[
+            ("MUIVerb", Sz("@shell32.dll,-51608")),
+            ("CommandStateHandler", Sz(PIN_HANDLER)),
+            ("NeverDefault", Sz("")),
+            ("SkipCloudDownload", Dword(0)),
+        ],
+    ),
+    (
+        r"*\shell\pintohomefile\command",
+        &[("DelegateExecute", Sz(PIN_HANDLER))],
+    ),
+];
+
+impl Sandbox {
+    /// Creates an empty sandbox and makes it active on the current thread.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let sandbox = win_ctx::testing::Sandbox::new()?;
+    /// let entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    /// ```
+    pub fn new() -> Result<Sandbox> {
+        let id = NEXT_SANDBOX.fetch_add(1, Ordering::Relaxed);
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let path = format!("{SANDBOX_PATH}\\{}-{time}-{id}", process::id());
+
+        RegKey::predef(HKEY_CURRENT_USER)
+            .create_subkey(&path)
+            .context("creating key", &path)?;
+        SANDBOXES.with(|s| s.borrow_mut().push(path.clone()));
+
+        Ok(Sandbox {
+            path,
+            _thread: PhantomData,
+        })
+    }
+
+    /// Creates a sandbox holding the shell keys of the given fixture and
+    /// makes it active on the current thread.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let sandbox = Sandbox::with_fixture(Fixture::Windows10)?;
+    /// ```
+    pub fn with_fixture(fixture: Fixture) -> Result<Sandbox> {
+        let sandbox = Sandbox::new()?;
+        sandbox.load(fixture)?;
+        Ok(sandbox)
+    }
+
+    /// Adds the shell keys of the given fixture to the sandbox.
+    pub fn load(&self, fixture: Fixture) -> Result<()> {
+        let root = self.root()?;
+
+        for (path, values) in fixture.keys() {
+            let (key, _) = root.create_subkey(path).context("creating key", path)?;
+
+            for (name, value) in *values {
+                let value = match value {
+                    Sz(s) => RegValue::Sz(s.to_string()),
+                    ExpandSz(s) => RegValue::ExpandSz(s.to_string()),
+                    Dword(n) => RegValue::Dword(*n),
+                };
+                key.set_raw_value(name, &value.to_raw()).value_context(
+                    "writing value",
+                    path,
+                    name,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Opens the sandbox root, which stands in for `HKEY_CLASSES_ROOT`
+    /// when checking keys directly.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    /// let key = sandbox.root()?.open_subkey(entry.path())?;
+    /// ```
+    pub fn root(&self) -> Result<RegKey> {
+        RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey_with_flags(&self.path, KEY_ALL_ACCESS)
+            .context("opening key", &self.path)
+    }
+
+    /// Gets the path of the sandbox root under `HKEY_CURRENT_USER`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        SANDBOXES.with(|s| s.borrow_mut().retain(|p| *p != self.path));
+        let _ = RegKey::predef(HKEY_CURRENT_USER).delete_subkey_all(&self.path);
+    }
+}
+
+impl Fixture {
+    /// Gets the names of the root entries the fixture has for the given
+    /// activation type, in menu order.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let names = Fixture::Windows10.entry_names(&ActivationType::Folder);
+    /// assert_eq!(names, ["cmd", "find", "Powershell", "UpdateEncryptionSettings"]);
+    /// ```
+    pub fn entry_names(&self, entry_type: &ActivationType) -> Vec<&'static str> {
+        let prefix = format!("{}\\shell\\", path::get_base_path(entry_type));
+        let mut names: Vec<&str> = Vec::new();
+
+        for (path, _) in self.keys() {
+            if let Some(rest) = path.strip_prefix(&prefix) {
+                let name = rest.split('\\').next().unwrap();
+
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        names.sort_by_key(|name| name.to_lowercase());
+        names
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &'static (&'static str, Values)> {
+        let extra = match self {
+            Fixture::Windows10 => &[][..],
+            Fixture::Windows11 => WINDOWS_11,
+        };
+
+        WINDOWS_10.iter().chain(extra)
+    }
+}
+
+// Checks whether a sandbox is active on the current thread.
+pub(crate) fn sandbox_active() -> bool {
+    SANDBOXES.with(|s| !s.borrow().is_empty())
+}
+
+// Opens the root of the innermost sandbox active on the current thread,
+// failing if its key was deleted while the sandbox is active.
+pub(crate) fn sandbox_root() -> Option<Result<RegKey>> {
+    SANDBOXES.with(|s| {
+        let sandboxes = s.borrow();
+        let path = sandboxes.last()?;
+
+        Some(
+            RegKey::predef(HKEY_CURRENT_USER)
+                .open_subkey_with_flags(path, KEY_ALL_ACCESS)
+                .context("opening key", path),
+        )
+    })
+}
+
+// Opens the key standing in for the given hive of the classic menu setting
+// in the innermost sandbox active on the current thread.
+pub(crate) fn sandbox_hive(scope: Scope) -> Option<Result<RegKey>> {
+    let name = match scope {
+        Scope::User => "HKEY_CURRENT_USER",
+        Scope::Machine => "HKEY_LOCAL_MACHINE",
+    };
+
+    Some(sandbox_root()?.and_then(|root| {
+        root.create_subkey(name)
+            .map(|(key, _)| key)
+            .context("creating key", name)
+    }))
+}
+
+/// Deletes an entry and its children, panicking on failure.
+///
+/// # Examples
+///
+/// ```no_run
+/// let entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+/// win_ctx::testing::cleanup_entry(entry);
+/// ```
+#[track_caller]
+pub fn cleanup_entry(entry: CtxEntry) {
+    let path = entry.path();
+    entry::hkcr()
+        .and_then(|root| root.delete_subkey_all(&path).context("deleting key", &path))
+        .unwrap_or_else(|e| panic!("Failed to clean up {path}: {e}"));
+}
+
+/// Asserts that an entry's `command` key has the given command, or that
+/// it has no `command` key. The key is read directly rather than with
+/// [`CtxEntry::command`].
+///
+/// # Examples
+///
+/// ```no_run
+/// win_ctx::testing::assert_command(&entry, Some("cmd.exe"));
+/// ```
+#[track_caller]
+pub fn assert_command(entry: &CtxEntry, expected: Option<&str>) {
+    let path = format!("{}\\command", entry.path());
+    let command = entry::hkcr()
+        .ok()
+        .and_then(|root| root.open_subkey(&path).ok())
+        .map(|key| key.get_value::<String, _>("").unwrap_or_default());
+
+    assert_eq!(
+        command.as_deref(),
+        expected,
+        "Entry {} has an unexpected command",
+        entry.address()
+    );
+}
+
+/// Asserts that a value on an entry's key has the given value, or that it
+/// does not exist. The value is read directly rather than with
+/// [`CtxEntry::get_value`].
+///
+/// # Examples
+///
+/// ```no_run
+/// win_ctx::testing::assert_value(&entry, "Extended", Some(&RegValue::Sz(String::new())));
+/// ```
+#[track_caller]
+pub fn assert_value(entry: &CtxEntry, name: &str, expected: Option<&RegValue>) {
+    let value = entry::hkcr()
+        .and_then(|root| {
+            root.open_subkey(entry.path())
+                .context("opening key", &entry.path())
+        })
+        .unwrap_or_else(|e| panic!("Entry {} is missing: {e}", entry.address()))
+        .get_raw_value(name)
+        .ok()
+        .map(|raw| RegValue::from_raw(&raw));
+
+    assert_eq!(
+        value.as_ref(),
+        expected,
+        "Entry {} has an unexpected {name:?} value",
+        entry.address()
+    );
+}
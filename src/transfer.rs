@@ -0,0 +1,230 @@
+use super::entry::{get_key, hkcr, read_key};
+use super::error::{Error, Result, ResultExt};
+use super::notify;
+use super::path::get_full_path;
+use super::*;
+use winreg::RegKey;
+
+/// Location to move or copy an entry to
+#[derive(Debug, Clone, Copy)]
+pub enum Destination<'a> {
+    /// Under an existing entry
+    Parent(&'a CtxEntry),
+    /// At the top level of an activation type
+    Root(&'a ActivationType),
+}
+
+/// How to handle an existing entry with the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Fail with `AlreadyExists`
+    #[default]
+    Fail,
+    /// Replace the existing entry and its children. The existing entry is
+    /// kept if copying fails.
+    Overwrite,
+    /// Keep the existing entry, replacing any values and children
+    /// with the same names
+    Merge,
+}
+
+impl CtxEntry {
+    /// Copies the entry and all of its children to the given destination,
+    /// keeping the entry's name.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    /// let copy = entry.copy_to(
+    ///     Destination::Root(&ActivationType::Background),
+    ///     ConflictPolicy::Fail,
+    /// )?;
+    /// ```
+    pub fn copy_to(&self, dest: Destination, policy: ConflictPolicy) -> Result<CtxEntry> {
+        let _batch = notify::begin_batch();
+        let (copy, _) = self.copy_key(dest, policy)?;
+
+        if let Destination::Parent(parent) = dest {
+            parent.write_value("Subcommands", &"")?;
+        }
+
+        Ok(copy)
+    }
+
+    /// Moves the entry and all of its children to the given destination,
+    /// keeping the entry's name. If the entry cannot be deleted after it was
+    /// copied, a new copy is deleted again as long as the entry is intact,
+    /// and both are kept otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    /// let parent = CtxEntry::new("Parent entry", &ActivationType::Folder)?;
+    /// entry.move_to(Destination::Parent(&parent), ConflictPolicy::Merge)?;
+    /// ```
+    pub fn move_to(&mut self, dest: Destination, policy: ConflictPolicy) -> Result<()> {
+        let _batch = notify::begin_batch();
+        let old_parent = self.parent()?;
+        let before = self.snapshot()?;
+        let (moved, created) = self.copy_key(dest, policy)?;
+
+        let path = self.path();
+        let deleted = hkcr()?
+            .delete_subkey_all(&path)
+            .context("deleting key", &path);
+
+        if let Err(e) = deleted {
+            // The copy may be all that is left of a partially deleted entry.
+            if created && self.snapshot().ok() == Some(before) {
+                let _ = moved.delete();
+            }
+
+            return Err(e);
+        }
+
+        notify::changed();
+
+        if let Destination::Parent(parent) = dest {
+            parent.write_value("Subcommands", &"")?;
+        }
+
+        if let Some(parent) = old_parent {
+            parent.remove_empty_subcommands()?;
+        }
+
+        *self = moved;
+        Ok(())
+    }
+
+    // Copies the entry's key to the destination without marking a parent
+    // as having children, and returns the copy and whether it is new.
+    fn copy_key(&self, dest: Destination, policy: ConflictPolicy) -> Result<(CtxEntry, bool)> {
+        let (name_path, entry_type) = self.destination_path(dest)?;
+        let path = get_full_path(&entry_type, &name_path);
+        let exists = read_key(&path).is_ok();
+
+        if exists && policy == ConflictPolicy::Fail {
+            return Err(Error::already_exists().with_context("copying key", &path, None));
+        }
+
+        if exists && policy == ConflictPolicy::Overwrite {
+            self.copy_over(&path)?;
+        } else {
+            let (dest_key, _) = hkcr()?
+                .create_subkey(&path)
+                .context("creating key", &path)?;
+            let copied = self.key().and_then(|source| {
+                source
+                    .copy_tree("", &dest_key)
+                    .context("copying key", &self.path())
+            });
+
+            if let Err(e) = copied {
+                if !exists {
+                    let _ = hkcr().map(|root| root.delete_subkey_all(&path));
+                }
+                return Err(e);
+            }
+        }
+
+        notify::changed();
+
+        let copy = CtxEntry {
+            name_path,
+            entry_type,
+            handle: None,
+        };
+        Ok((copy, !exists))
+    }
+
+    // Replaces the key at the given path with a copy of the entry. The copy
+    // is made under a temporary name and swapped in, so the existing key is
+    // kept if anything fails.
+    fn copy_over(&self, path: &str) -> Result<()> {
+        let (parent_path, name) = path.rsplit_once('\\').unwrap();
+        let parent_key = get_key(parent_path)?;
+        let temp_name = format!("~copy-{name}");
+        let source = self.key()?;
+
+        let result = parent_key
+            .create_subkey(&temp_name)
+            .and_then(|(temp_key, _)| source.copy_tree("", &temp_key))
+            .context("copying key", &self.path())
+            .and_then(|_| replace_key(&parent_key, parent_path, &temp_name, name));
+
+        if result.is_err() {
+            let _ = parent_key.delete_subkey_all(&temp_name);
+        }
+
+        result
+    }
+
+    // Resolves the name path and type of the entry at the destination.
+    fn destination_path(&self, dest: Destination) -> Result<(Vec<String>, ActivationType)> {
+        let name = self.name()?;
+
+        let (mut name_path, entry_type) = match dest {
+            Destination::Parent(parent) => {
+                let _ = parent.key()?;
+
+                if parent.entry_type == self.entry_type
+                    && parent.name_path.len() >= self.name_path.len()
+                    && same_names(&parent.name_path[..self.name_path.len()], &self.name_path)
+                {
+                    return Err(Error::invalid_value(
+                        "cannot move or copy an entry into itself",
+                    ));
+                }
+
+                (parent.name_path.clone(), parent.entry_type.clone())
+            }
+            Destination::Root(entry_type) => (Vec::new(), entry_type.clone()),
+        };
+
+        name_path.push(name);
+
+        if entry_type == self.entry_type && same_names(&name_path, &self.name_path) {
+            return Err(Error::invalid_value("entry is already at the destination"));
+        }
+
+        Ok((name_path, entry_type))
+    }
+}
+
+// Compares name paths ignoring case, as registry keys are.
+fn same_names(a: &[String], b: &[String]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.to_lowercase() == b.to_lowercase())
+}
+
+// Renames the subkey `from` to `to`, replacing the existing `to`. The
+// existing key is renamed to a backup name until the rename succeeds, so it
+// is restored if anything fails.
+pub(crate) fn replace_key(
+    parent_key: &RegKey,
+    parent_path: &str,
+    from: &str,
+    to: &str,
+) -> Result<()> {
+    let backup = format!("~backup-{to}");
+
+    parent_key
+        .rename_subkey(to, &backup)
+        .context("renaming key", &format!("{parent_path}\\{to}"))?;
+
+    if let Err(e) = parent_key
+        .rename_subkey(from, to)
+        .context("renaming key", &format!("{parent_path}\\{from}"))
+    {
+        let _ = parent_key.rename_subkey(&backup, to);
+        return Err(e);
+    }
+
+    parent_key
+        .delete_subkey_all(&backup)
+        .context("deleting key", &format!("{parent_path}\\{backup}"))
+}
@@ -1,45 +1,298 @@
+use crate::error::{Result, ResultExt};
+use crate::notify;
 use crate::path::CTX_MENU_PATH;
-use std::io::{self, ErrorKind};
-use winreg::{RegKey, enums::HKEY_CURRENT_USER};
+use std::io::ErrorKind;
+use winreg::{
+    RegKey,
+    enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE},
+};
 
-const HKCU: RegKey = RegKey::predef(HKEY_CURRENT_USER);
+/// Where a setting applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// The current user only
+    User,
+    /// All users (requires administrator privileges to change)
+    Machine,
+}
+
+/// State of the pre-Windows 11 context menu setting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassicMenuState {
+    /// Whether the classic menu is enabled for the current user
+    pub user: bool,
+    /// Whether the classic menu is enabled for all users
+    pub machine: bool,
+    /// Whether the current user has an `InprocServer32` key, which takes
+    /// precedence over the machine-wide key whatever its value
+    pub user_override: bool,
+    user_raw: RawState,
+    machine_raw: RawState,
+}
+
+// Raw registry state of one scope, kept so it can be restored exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RawState {
+    clsid_exists: bool,
+    // Default value of the `InprocServer32` key, if the key exists.
+    inproc_default: Option<Option<String>>,
+}
+
+impl ClassicMenuState {
+    /// Gets whether the classic menu is in effect for the current user.
+    pub fn enabled(&self) -> bool {
+        self.user || (!self.user_override && self.machine)
+    }
+
+    /// Restores the setting in both scopes to exactly this state.
+    /// You must restart explorer.exe (e.g., with [`restart_explorer`](crate::restart_explorer))
+    /// for changes to take effect.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let previous = win_ctx::set_classic_menu(true, Scope::User)?;
+    /// previous.restore()?;
+    /// ```
+    pub fn restore(&self) -> Result<()> {
+        restore_raw(&hive(Scope::User)?, &self.user_raw)?;
+        let machine = hive(Scope::Machine)?;
+
+        // Avoid requiring administrator privileges when nothing changed.
+        if read_raw(&machine)? != self.machine_raw {
+            restore_raw(&machine, &self.machine_raw)?;
+        }
 
-/// Enable or disable the pre-Windows 11 context menu.
-/// You must restart explorer.exe for changes to take effect.
+        notify::changed();
+        Ok(())
+    }
+}
+
+/// Gets the state of the pre-Windows 11 context menu setting.
 ///
 /// # Examples
 ///
 /// ```no_run
-/// win_ctx::toggle_classic_menu(true)?;
+/// let state = win_ctx::classic_menu_state()?;
+/// println!("Classic menu enabled: {}", state.enabled());
+/// ```
+pub fn classic_menu_state() -> Result<ClassicMenuState> {
+    let user_raw = read_raw(&hive(Scope::User)?)?;
+    let machine_raw = read_raw(&hive(Scope::Machine)?)?;
+
+    Ok(ClassicMenuState {
+        user: user_raw.is_enabled(),
+        machine: machine_raw.is_enabled(),
+        user_override: user_raw.inproc_default.is_some(),
+        user_raw,
+        machine_raw,
+    })
+}
+
+/// Enables or disables the pre-Windows 11 context menu in the given scope
+/// and returns the previous state, which can be restored later. Disabling
+/// only removes the empty `InprocServer32` key that enabling creates, so
+/// other registrations of the class are left intact.
+/// You must restart explorer.exe (e.g., with [`restart_explorer`](crate::restart_explorer))
+/// for changes to take effect.
+///
+/// # Examples
+///
+/// ```no_run
+/// let previous = win_ctx::set_classic_menu(true, Scope::Machine)?;
 /// ```
-pub fn toggle_classic_menu(enable: bool) -> io::Result<()> {
+pub fn set_classic_menu(enable: bool, scope: Scope) -> Result<ClassicMenuState> {
+    let previous = classic_menu_state()?;
+    let hive = &hive(scope)?;
+
+    let path = format!("{CTX_MENU_PATH}\\InprocServer32");
+
     if enable {
-        let path = format!("{}\\InprocServer32\\", CTX_MENU_PATH);
-        let (key, _) = HKCU.create_subkey(path).unwrap();
+        let (key, _) = hive.create_subkey(&path).context("creating key", &path)?;
         key.set_value("", &"")
-    } else {
-        match HKCU.delete_subkey_all(CTX_MENU_PATH) {
+            .value_context("writing value", &path, "")?;
+    } else if read_raw(hive)?.is_enabled() {
+        delete_tree(hive, &path)?;
+    }
+
+    notify::changed();
+    Ok(previous)
+}
+
+/// Enable or disable the pre-Windows 11 context menu for the current user.
+/// You must restart explorer.exe (e.g., with [`restart_explorer`](crate::restart_explorer))
+/// for changes to take effect.
+///
+/// # Examples
+///
+/// ```no_run
+/// win_ctx::toggle_classic_menu(true)?;
+/// ```
+pub fn toggle_classic_menu(enable: bool) -> Result<()> {
+    set_classic_menu(enable, Scope::User).map(|_| ())
+}
+
+// Opens the root of the given scope's hive, which is redirected while a test
+// sandbox is active.
+fn hive(scope: Scope) -> Result<RegKey> {
+    #[cfg(feature = "testing")]
+    if let Some(hive) = crate::testing::sandbox_hive(scope) {
+        return hive;
+    }
+
+    Ok(match scope {
+        Scope::User => RegKey::predef(HKEY_CURRENT_USER),
+        Scope::Machine => RegKey::predef(HKEY_LOCAL_MACHINE),
+    })
+}
+
+impl RawState {
+    fn is_enabled(&self) -> bool {
+        self.inproc_default
+            .as_ref()
+            .is_some_and(|v| v.as_deref() == Some(""))
+    }
+}
+
+fn read_raw(hive: &RegKey) -> Result<RawState> {
+    let clsid_key = match hive.open_subkey(CTX_MENU_PATH) {
+        Ok(key) => key,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Ok(RawState {
+                clsid_exists: false,
+                inproc_default: None,
+            });
+        }
+        Err(e) => return Err(e).context("opening key", CTX_MENU_PATH),
+    };
+
+    let inproc_default = match clsid_key.open_subkey("InprocServer32") {
+        Ok(key) => Some(key.get_value::<String, _>("").ok()),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(e).context("opening key", &format!("{CTX_MENU_PATH}\\InprocServer32"));
+        }
+    };
+
+    Ok(RawState {
+        clsid_exists: true,
+        inproc_default,
+    })
+}
+
+fn restore_raw(hive: &RegKey, raw: &RawState) -> Result<()> {
+    if !raw.clsid_exists {
+        return delete_tree(hive, CTX_MENU_PATH);
+    }
+
+    let path = format!("{CTX_MENU_PATH}\\InprocServer32");
+
+    let Some(default) = &raw.inproc_default else {
+        return delete_tree(hive, &path);
+    };
+
+    let (key, _) = hive.create_subkey(&path).context("creating key", &path)?;
+
+    match default {
+        Some(value) => key
+            .set_value("", value)
+            .value_context("writing value", &path, ""),
+        None => match key.delete_value("") {
             Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
-            Err(e) => Err(e),
+            Err(e) => Err(e).value_context("deleting value", &path, ""),
             Ok(_) => Ok(()),
-        }
+        },
+    }
+}
+
+// Deletes a key and its subkeys without erroring if nonexistent.
+fn delete_tree(hive: &RegKey, path: &str) -> Result<()> {
+    match hive.delete_subkey_all(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).context("deleting key", path),
+        Ok(_) => Ok(()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::Sandbox;
 
     #[test]
     fn disable_enable_classic_menu() {
+        let _sandbox = Sandbox::new().unwrap();
+        let hkcu = hive(Scope::User).unwrap();
         toggle_classic_menu(false).expect("Failed to disable classic menu");
         toggle_classic_menu(false).expect("Duplicate menu disable call should be ok");
-        HKCU.open_subkey(CTX_MENU_PATH)
+        hkcu.open_subkey(format!("{CTX_MENU_PATH}\\InprocServer32"))
             .expect_err("Classic menu key should not exist");
 
         toggle_classic_menu(true).expect("Failed to enable classic menu");
         toggle_classic_menu(true).expect("Duplicate menu enable call should be ok");
-        HKCU.open_subkey(CTX_MENU_PATH)
+        hkcu.open_subkey(CTX_MENU_PATH)
             .expect("Classic menu key should exist");
     }
+
+    #[test]
+    fn classic_menu_state_and_restore() {
+        let _sandbox = Sandbox::new().unwrap();
+        let original = classic_menu_state().unwrap();
+
+        let previous = set_classic_menu(true, Scope::User).unwrap();
+        assert_eq!(previous, original);
+
+        let state = classic_menu_state().unwrap();
+        assert!(state.user);
+        assert!(state.user_override);
+        assert!(state.enabled());
+
+        set_classic_menu(false, Scope::User).unwrap();
+        let state = classic_menu_state().unwrap();
+        assert!(!state.user);
+        assert!(!state.user_override);
+        assert_eq!(state.enabled(), state.machine);
+
+        original.restore().unwrap();
+        assert_eq!(classic_menu_state().unwrap(), original);
+    }
+
+    #[test]
+    fn disable_keeps_registered_class() {
+        let _sandbox = Sandbox::new().unwrap();
+        let hklm = hive(Scope::Machine).unwrap();
+        let path = format!("{CTX_MENU_PATH}\\InprocServer32");
+        let (key, _) = hklm.create_subkey(&path).unwrap();
+        key.set_value("", &"shell32.dll").unwrap();
+
+        set_classic_menu(false, Scope::Machine).unwrap();
+        assert_eq!(key.get_value::<String, _>("").unwrap(), "shell32.dll");
+
+        set_classic_menu(true, Scope::Machine).unwrap();
+        set_classic_menu(false, Scope::Machine).unwrap();
+        hklm.open_subkey(&path)
+            .expect_err("Classic menu override should be deleted");
+        hklm.open_subkey(CTX_MENU_PATH)
+            .expect("Class key should be kept");
+    }
+
+    #[test]
+    fn classic_menu_override_needs_inproc_server() {
+        let _sandbox = Sandbox::new().unwrap();
+        let hkcu = hive(Scope::User).unwrap();
+        hkcu.create_subkey(CTX_MENU_PATH).unwrap();
+
+        let state = classic_menu_state().unwrap();
+        assert!(!state.user_override);
+        assert_eq!(state.enabled(), state.machine);
+
+        let path = format!("{CTX_MENU_PATH}\\InprocServer32");
+        let (key, _) = hkcu.create_subkey(&path).unwrap();
+        key.set_value("", &"shell32.dll").unwrap();
+
+        let state = classic_menu_state().unwrap();
+        assert!(state.user_override);
+        assert!(!state.user);
+        assert!(!state.enabled());
+    }
 }
@@ -0,0 +1,425 @@
+use super::command::{missing_executable, parse_command};
+use super::entry::MAX_NAME_LEN;
+use super::error::Result;
+use super::*;
+
+// Explorer shows at most this many static verbs in a cascade.
+const MAX_CASCADE_ITEMS: usize = 16;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// The entry may still work but likely not as intended
+    Warning,
+    /// The entry does not work as intended
+    Error,
+}
+
+/// Problem found by a [`Validator`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DiagnosticKind {
+    /// The command's executable does not exist
+    MissingExecutable,
+    /// The command's executable is an unquoted path containing spaces
+    UnquotedPath,
+    /// The command uses a placeholder not supported by the activation type
+    WrongPlaceholder,
+    /// An entry has both a command and children, so the command is ignored
+    CommandWithChildren,
+    /// An entry with children lacks the `Subcommands` value
+    MissingSubcommands,
+    /// A cascade has more items than Explorer shows
+    TooManyItems,
+    /// An entry name is longer than the registry allows
+    NameTooLong,
+    /// An entry name contains characters that cannot be used
+    InvalidCharacters,
+    /// Sibling entries show the same label
+    DuplicateLabel,
+}
+
+/// Problem found with an entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    /// Address of the entry with the problem
+    pub address: EntryAddress,
+    /// Description of the problem
+    pub message: String,
+    /// Suggested fix, if any
+    pub hint: Option<String>,
+}
+
+/// Checks entries for problems that keep them from working as intended
+///
+/// # Examples
+///
+/// ```no_run
+/// let snapshot = entry.snapshot()?;
+/// let diagnostics = Validator::new()
+///     .check_executables(false)
+///     .validate(&snapshot, &ActivationType::Folder);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Validator {
+    check_executables: bool,
+    max_items: usize,
+}
+
+impl Default for Validator {
+    fn default() -> Validator {
+        Validator {
+            check_executables: true,
+            max_items: MAX_CASCADE_ITEMS,
+        }
+    }
+}
+
+impl CtxEntry {
+    /// Checks the entry and its children for problems with the default
+    /// [`Validator`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::get(&["Open directory in"], &ActivationType::Background)?.unwrap();
+    ///
+    /// for diagnostic in entry.validate()? {
+    ///     println!("{}: {}", diagnostic.address, diagnostic.message);
+    /// }
+    /// ```
+    pub fn validate(&self) -> Result<Vec<Diagnostic>> {
+        Validator::new().validate_entry(self)
+    }
+}
+
+impl Validator {
+    /// Creates a validator with every check enabled.
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    /// Sets whether to check that command executables exist, which
+    /// depends on the machine running the check. Enabled by default.
+    pub fn check_executables(mut self, check: bool) -> Validator {
+        self.check_executables = check;
+        self
+    }
+
+    /// Sets the number of items in a cascade above which a warning is
+    /// reported. Defaults to 16, the most Explorer shows.
+    pub fn max_items(mut self, max: usize) -> Validator {
+        self.max_items = max;
+        self
+    }
+
+    /// Checks an entry description and its children as a top-level entry
+    /// with the given activation type.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let diagnostics = Validator::new().validate(&snapshot, &ActivationType::Background);
+    /// ```
+    pub fn validate(
+        &self,
+        snapshot: &EntrySnapshot,
+        entry_type: &ActivationType,
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        self.check(snapshot, entry_type, &[], &mut diagnostics);
+        diagnostics
+    }
+
+    /// Checks an existing entry and its children.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let diagnostics = Validator::new().max_items(10).validate_entry(&entry)?;
+    /// ```
+    pub fn validate_entry(&self, entry: &CtxEntry) -> Result<Vec<Diagnostic>> {
+        let snapshot = entry.snapshot()?;
+        let parent_path = &entry.name_path[..entry.name_path.len() - 1];
+        let mut diagnostics = Vec::new();
+        self.check(&snapshot, &entry.entry_type, parent_path, &mut diagnostics);
+        Ok(diagnostics)
+    }
+
+    fn check(
+        &self,
+        snapshot: &EntrySnapshot,
+        entry_type: &ActivationType,
+        parent_path: &[String],
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let mut name_path = parent_path.to_vec();
+        name_path.push(snapshot.name.clone());
+
+        let mut report = |severity, kind, message: String, hint: Option<&str>| {
+            diagnostics.push(Diagnostic {
+                severity,
+                kind,
+                address: EntryAddress {
+                    entry_type: entry_type.clone(),
+                    name_path: name_path.clone(),
+                },
+                message,
+                hint: hint.map(|h| h.to_string()),
+            })
+        };
+
+        let name = &snapshot.name;
+
+        if name.chars().count() > MAX_NAME_LEN {
+            report(
+                Severity::Error,
+                DiagnosticKind::NameTooLong,
+                format!("name is longer than {MAX_NAME_LEN} characters"),
+                Some("shorten the name and set a label instead"),
+            );
+        }
+        if name.is_empty() || name.contains(|c: char| c == '\\' || c.is_control()) {
+            report(
+                Severity::Error,
+                DiagnosticKind::InvalidCharacters,
+                format!("name {name:?} is empty or contains backslashes or control characters"),
+                Some("rename the entry and set a label with the characters instead"),
+            );
+        }
+
+        if let Some(command) = &snapshot.command {
+            let parsed = parse_command(command);
+
+            if !parsed.quoted && parsed.executable.contains(' ') {
+                report(
+                    Severity::Warning,
+                    DiagnosticKind::UnquotedPath,
+                    format!(
+                        "executable path {:?} contains spaces but is not quoted",
+                        parsed.executable
+                    ),
+                    Some("wrap the executable path in double quotes"),
+                );
+            }
+
+            if self.check_executables
+                && let Some(executable) = missing_executable(command)
+            {
+                report(
+                    Severity::Error,
+                    DiagnosticKind::MissingExecutable,
+                    format!("executable {executable:?} does not exist"),
+                    Some("fix the path or remove the entry"),
+                );
+            }
+
+            let lower = command.to_lowercase();
+            if *entry_type == ActivationType::Background
+                && (lower.contains("%1") || lower.contains("%l"))
+            {
+                report(
+                    Severity::Error,
+                    DiagnosticKind::WrongPlaceholder,
+                    "`%1` and `%L` are empty on directory backgrounds".to_string(),
+                    Some("use `%V` for the directory path"),
+                );
+            }
+
+            if !snapshot.children.is_empty() {
+                report(
+                    Severity::Warning,
+                    DiagnosticKind::CommandWithChildren,
+                    "entry has both a command and children, so the command is ignored".to_string(),
+                    Some("remove the command or the children"),
+                );
+            }
+        }
+
+        if !snapshot.children.is_empty() {
+            let has_subcommands = snapshot
+                .values
+                .keys()
+                .any(|v| v.eq_ignore_ascii_case("Subcommands"));
+
+            if !has_subcommands {
+                report(
+                    Severity::Error,
+                    DiagnosticKind::MissingSubcommands,
+                    "entry has children but no `Subcommands` value, so they are hidden".to_string(),
+                    Some("set an empty `Subcommands` value"),
+                );
+            }
+        }
+
+        if snapshot.children.len() > self.max_items {
+            report(
+                Severity::Warning,
+                DiagnosticKind::TooManyItems,
+                format!(
+                    "cascade has {} items, more than the {} shown",
+                    snapshot.children.len(),
+                    self.max_items
+                ),
+                Some("split the items into nested cascades"),
+            );
+        }
+
+        let labels: Vec<String> = snapshot
+            .children
+            .iter()
+            .map(|c| c.label.as_ref().unwrap_or(&c.name).to_lowercase())
+            .collect();
+
+        for (i, child) in snapshot.children.iter().enumerate() {
+            if labels[..i].contains(&labels[i]) {
+                let mut child_path = name_path.clone();
+                child_path.push(child.name.clone());
+
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    kind: DiagnosticKind::DuplicateLabel,
+                    address: EntryAddress {
+                        entry_type: entry_type.clone(),
+                        name_path: child_path,
+                    },
+                    message: format!(
+                        "label {:?} is shown by an earlier sibling",
+                        child.label.as_ref().unwrap_or(&child.name)
+                    ),
+                    hint: Some("give each sibling a distinct label".to_string()),
+                });
+            }
+        }
+
+        for child in &snapshot.children {
+            self.check(child, entry_type, &name_path, diagnostics);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_command(name: &str, command: &str) -> EntrySnapshot {
+        EntrySnapshot {
+            command: Some(command.to_string()),
+            ..EntrySnapshot::tree(name, Vec::new())
+        }
+    }
+
+    fn cascade(name: &str, children: Vec<EntrySnapshot>) -> EntrySnapshot {
+        let mut parent = EntrySnapshot::tree(name, Vec::new());
+        parent
+            .values
+            .insert("Subcommands".to_string(), RegValue::Sz(String::new()));
+        parent.children = children;
+        parent
+    }
+
+    fn kinds(snapshot: &EntrySnapshot, entry_type: &ActivationType) -> Vec<DiagnosticKind> {
+        Validator::new()
+            .check_executables(false)
+            .validate(snapshot, entry_type)
+            .into_iter()
+            .map(|d| d.kind)
+            .collect()
+    }
+
+    #[test]
+    fn valid_entries() {
+        let entry = cascade(
+            "Open in",
+            vec![
+                with_command("Terminal", r#"cmd /s /k pushd "%V""#),
+                with_command("Editor", r#""C:\Program Files\Editor\editor.exe" "%V""#),
+            ],
+        );
+        assert_eq!(kinds(&entry, &ActivationType::Background), []);
+    }
+
+    #[test]
+    fn command_problems() {
+        let entry = with_command("Open", r"C:\Program Files\Tool\tool.exe %1");
+        assert_eq!(
+            kinds(&entry, &ActivationType::Background),
+            [
+                DiagnosticKind::UnquotedPath,
+                DiagnosticKind::WrongPlaceholder
+            ]
+        );
+        assert_eq!(
+            kinds(&entry, &ActivationType::File(".txt".to_string())),
+            [DiagnosticKind::UnquotedPath]
+        );
+
+        let diagnostics = Validator::new().validate(
+            &with_command("Open", r#""C:\missing\tool.exe" "%1""#),
+            &ActivationType::Folder,
+        );
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::MissingExecutable);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].address.to_string(), "folder:Open");
+        assert!(diagnostics[0].hint.is_some());
+    }
+
+    #[test]
+    fn cascade_problems() {
+        let mut entry = cascade("Open in", vec![EntrySnapshot::tree("Child", Vec::new())]);
+        entry.command = Some("cmd.exe".to_string());
+        entry.values.clear();
+        assert_eq!(
+            kinds(&entry, &ActivationType::Folder),
+            [
+                DiagnosticKind::CommandWithChildren,
+                DiagnosticKind::MissingSubcommands
+            ]
+        );
+
+        let children = (0..17)
+            .map(|i| EntrySnapshot::tree(&i.to_string(), Vec::new()))
+            .collect();
+        let entry = cascade("Open in", children);
+        assert_eq!(
+            kinds(&entry, &ActivationType::Folder),
+            [DiagnosticKind::TooManyItems]
+        );
+    }
+
+    #[test]
+    fn name_and_label_problems() {
+        let mut labeled = EntrySnapshot::tree("02_Terminal", Vec::new());
+        labeled.label = Some("terminal".to_string());
+
+        let entry = cascade(
+            "Open in",
+            vec![
+                EntrySnapshot::tree("Terminal", Vec::new()),
+                labeled,
+                EntrySnapshot::tree(&"a".repeat(256), Vec::new()),
+                EntrySnapshot::tree("a\\b", Vec::new()),
+            ],
+        );
+
+        let diagnostics = Validator::new().validate(&entry, &ActivationType::Folder);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.kind, d.address.name_path.last().unwrap().as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (DiagnosticKind::DuplicateLabel, "02_Terminal"),
+                (DiagnosticKind::NameTooLong, "a".repeat(256).as_str()),
+                (DiagnosticKind::InvalidCharacters, "a\\b"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use super::CtxEntry;
+use super::error::{Result, ResultExt};
+use super::notify;
+use std::collections::BTreeMap;
+use std::io;
+use winreg::enums::*;
+
+/// Typed registry value
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RegValue {
+    /// `REG_NONE` (no data)
+    None,
+    /// `REG_SZ`
+    Sz(String),
+    /// `REG_EXPAND_SZ`
+    ExpandSz(String),
+    /// `REG_MULTI_SZ`
+    MultiSz(Vec<String>),
+    /// `REG_DWORD`
+    Dword(u32),
+    /// `REG_DWORD_BIG_ENDIAN`
+    DwordBigEndian(u32),
+    /// `REG_QWORD`
+    Qword(u64),
+    /// `REG_BINARY`
+    Binary(Vec<u8>),
+    /// Data of any other type, or data that does not fit its type (e.g.,
+    /// `REG_NONE` with data or a truncated `REG_DWORD`), kept as is. `ty`
+    /// is the type code, from 0 (`REG_NONE`) to 11 (`REG_QWORD`), and other
+    /// codes are written as `REG_BINARY`.
+    Raw { ty: u32, bytes: Vec<u8> },
+}
+
+impl RegValue {
+    // Converts a raw registry value, keeping unsupported types and
+    // malformed values as raw data of their original type.
+    pub(crate) fn from_raw(raw: &winreg::RegValue) -> RegValue {
+        let bytes = raw.bytes.as_slice();
+
+        let value = match raw.vtype {
+            REG_NONE if bytes.is_empty() => Some(RegValue::None),
+            REG_SZ => decode_str(bytes).map(RegValue::Sz),
+            REG_EXPAND_SZ => decode_str(bytes).map(RegValue::ExpandSz),
+            REG_MULTI_SZ => decode_str(bytes).map(|s| {
+                let mut strings: Vec<String> = s.split('\0').map(|s| s.to_string()).collect();
+                if strings.len() == 1 && strings[0].is_empty() {
+                    strings.clear();
+                }
+                RegValue::MultiSz(strings)
+            }),
+            REG_DWORD => bytes
+                .try_into()
+                .ok()
+                .map(|b| RegValue::Dword(u32::from_le_bytes(b))),
+            REG_DWORD_BIG_ENDIAN => bytes
+                .try_into()
+                .ok()
+                .map(|b| RegValue::DwordBigEndian(u32::from_be_bytes(b))),
+            REG_QWORD => bytes
+                .try_into()
+                .ok()
+                .map(|b| RegValue::Qword(u64::from_le_bytes(b))),
+            REG_BINARY => Some(RegValue::Binary(bytes.to_vec())),
+            _ => None,
+        };
+
+        value.unwrap_or_else(|| RegValue::Raw {
+            ty: raw.vtype.clone() as u32,
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    pub(crate) fn to_raw(&self) -> winreg::RegValue {
+        let (bytes, vtype) = match self {
+            RegValue::None => (Vec::new(), REG_NONE),
+            RegValue::Sz(s) => (encode_str(s), REG_SZ),
+            RegValue::ExpandSz(s) => (encode_str(s), REG_EXPAND_SZ),
+            RegValue::MultiSz(strings) => {
+                let mut bytes: Vec<u8> = strings.iter().flat_map(|s| encode_str(s)).collect();
+                bytes.extend([0, 0]);
+                (bytes, REG_MULTI_SZ)
+            }
+            RegValue::Dword(n) => (n.to_le_bytes().to_vec(), REG_DWORD),
+            RegValue::DwordBigEndian(n) => (n.to_be_bytes().to_vec(), REG_DWORD_BIG_ENDIAN),
+            RegValue::Qword(n) => (n.to_le_bytes().to_vec(), REG_QWORD),
+            RegValue::Binary(bytes) => (bytes.clone(), REG_BINARY),
+            RegValue::Raw { ty, bytes } => (bytes.clone(), reg_type(*ty)),
+        };
+
+        winreg::RegValue { bytes, vtype }
+    }
+}
+
+impl RegValue {
+    /// Gets the string of an `Sz` or `ExpandSz` value.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RegValue::Sz(s) | RegValue::ExpandSz(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl CtxEntry {
+    /// Gets a registry value on the entry's key, if it exists. Pass `""`
+    /// for the key's default value.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    /// let value = entry.get_value("AppliesTo")?;
+    /// ```
+    pub fn get_value(&self, name: &str) -> Result<Option<RegValue>> {
+        match self.key()?.get_raw_value(name) {
+            Ok(raw) => Ok(Some(RegValue::from_raw(&raw))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).value_context("reading value", &self.path(), name),
+        }
+    }
+
+    /// Sets a registry value on the entry's key, replacing any existing
+    /// value with the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    /// entry.set_value("AppliesTo", &RegValue::Sz("System.FileName:\"*.rs\"".to_string()))?;
+    /// ```
+    pub fn set_value(&mut self, name: &str, value: &RegValue) -> Result<()> {
+        let path = self.path();
+        self.write_key()?
+            .set_raw_value(name, &value.to_raw())
+            .value_context("writing value", &path, name)
+            .inspect(|_| notify::changed())
+    }
+
+    /// Deletes a registry value from the entry's key without erroring if
+    /// it does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    /// entry.delete_value("AppliesTo")?;
+    /// ```
+    pub fn delete_value(&mut self, name: &str) -> Result<()> {
+        self.safe_delete_value(name)
+    }
+
+    /// Gets every registry value on the entry's key, including those
+    /// represented by other functions (e.g., `Icon`).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    ///
+    /// for (name, value) in entry.values()? {
+    ///     println!("{name}: {value:?}");
+    /// }
+    /// ```
+    pub fn values(&self) -> Result<BTreeMap<String, RegValue>> {
+        let mut values = BTreeMap::new();
+
+        for value in self.key()?.enum_values() {
+            let (name, raw) = value.context("listing values", &self.path())?;
+            values.insert(name, RegValue::from_raw(&raw));
+        }
+
+        Ok(values)
+    }
+}
+
+// Decodes UTF-16LE data, trimming trailing null characters. Fails if the
+// data is not valid UTF-16.
+fn decode_str(bytes: &[u8]) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let words: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let s = String::from_utf16(&words).ok()?;
+    Some(s.trim_end_matches('\0').to_string())
+}
+
+fn reg_type(ty: u32) -> RegType {
+    match ty {
+        0 => REG_NONE,
+        1 => REG_SZ,
+        2 => REG_EXPAND_SZ,
+        4 => REG_DWORD,
+        5 => REG_DWORD_BIG_ENDIAN,
+        6 => REG_LINK,
+        7 => REG_MULTI_SZ,
+        8 => REG_RESOURCE_LIST,
+        9 => REG_FULL_RESOURCE_DESCRIPTOR,
+        10 => REG_RESOURCE_REQUIREMENTS_LIST,
+        11 => REG_QWORD,
+        _ => REG_BINARY,
+    }
+}
+
+// Encodes a string as null-terminated UTF-16LE data.
+fn encode_str(s: &str) -> Vec<u8> {
+    s.encode_utf16()
+        .chain([0])
+        .flat_map(|w| w.to_le_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: RegValue) {
+        assert_eq!(RegValue::from_raw(&value.to_raw()), value);
+    }
+
+    #[test]
+    fn round_trip_values() {
+        round_trip(RegValue::None);
+        round_trip(RegValue::Sz("cmd /s /k pushd \"%V\"".to_string()));
+        round_trip(RegValue::Sz(String::new()));
+        round_trip(RegValue::ExpandSz("%SystemRoot%\\System32".to_string()));
+        round_trip(RegValue::MultiSz(vec!["a".to_string(), "b".to_string()]));
+        round_trip(RegValue::MultiSz(Vec::new()));
+        round_trip(RegValue::Dword(0xdeadbeef));
+        round_trip(RegValue::DwordBigEndian(0xdeadbeef));
+        round_trip(RegValue::Qword(u64::MAX));
+        round_trip(RegValue::Binary(vec![1, 2, 3]));
+    }
+
+    fn raw_round_trip(bytes: Vec<u8>, vtype: RegType) -> RegValue {
+        let raw = winreg::RegValue { bytes, vtype };
+        let value = RegValue::from_raw(&raw);
+        let written = value.to_raw();
+
+        assert_eq!(written.vtype, raw.vtype);
+        assert_eq!(written.bytes, raw.bytes);
+        value
+    }
+
+    #[test]
+    fn malformed_values_keep_type() {
+        assert_eq!(
+            raw_round_trip(vec![1, 2], REG_DWORD),
+            RegValue::Raw {
+                ty: 4,
+                bytes: vec![1, 2],
+            }
+        );
+        assert_eq!(
+            raw_round_trip(vec![1, 2, 3], REG_QWORD),
+            RegValue::Raw {
+                ty: 11,
+                bytes: vec![1, 2, 3],
+            }
+        );
+        assert_eq!(
+            raw_round_trip(vec![1, 2, 3], REG_NONE),
+            RegValue::Raw {
+                ty: 0,
+                bytes: vec![1, 2, 3],
+            }
+        );
+        raw_round_trip(vec![0x41, 0, 0x42], REG_SZ);
+        raw_round_trip(vec![0x00, 0xd8, 0, 0], REG_EXPAND_SZ);
+        raw_round_trip(vec![1, 2, 3, 4], REG_LINK);
+    }
+
+    #[test]
+    fn qword_round_trip() {
+        assert_eq!(
+            raw_round_trip(42u64.to_le_bytes().to_vec(), REG_QWORD),
+            RegValue::Qword(42)
+        );
+    }
+
+    #[test]
+    fn big_endian_dword() {
+        let raw = winreg::RegValue {
+            bytes: vec![0, 0, 0, 1],
+            vtype: REG_DWORD_BIG_ENDIAN,
+        };
+        assert_eq!(RegValue::from_raw(&raw), RegValue::DwordBigEndian(1));
+        assert_eq!(RegValue::DwordBigEndian(1).to_raw().bytes, raw.bytes);
+    }
+}
@@ -0,0 +1,170 @@
+use super::entry::hkcr;
+use super::error::{Error, Result, ResultExt};
+use super::path::get_full_path;
+use super::*;
+use std::io;
+
+/// Lazy depth-first iterator over entries, in menu order.
+///
+/// Errors reading a key are yielded as items, and the walk continues with
+/// the remaining entries.
+///
+/// # Examples
+///
+/// ```no_run
+/// let mut walk = CtxEntry::walk_type(&ActivationType::Background);
+///
+/// while let Some(item) = walk.next() {
+///     let item = item?;
+///
+///     if item.entry.name()? == "Open directory in" {
+///         walk.skip_children();
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Walk {
+    entry_type: ActivationType,
+    stack: Vec<Result<(Vec<String>, usize)>>,
+    // Name path of the last yielded entry and the depth of its children,
+    // which are read on the next call unless skipped.
+    current: Option<(Vec<String>, usize)>,
+    max_depth: Option<usize>,
+}
+
+/// Entry yielded by a walk
+#[derive(Debug)]
+pub struct WalkEntry {
+    pub entry: CtxEntry,
+    /// Depth of the entry relative to where the walk started, starting at 0
+    pub depth: usize,
+}
+
+impl WalkEntry {
+    /// Gets the path to the entry as a list of entry names.
+    pub fn path(&self) -> &[String] {
+        &self.entry.name_path
+    }
+}
+
+impl CtxEntry {
+    /// Walks the entry and all of its descendants. The entry itself is
+    /// yielded first with a depth of 0.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let entry = CtxEntry::new("Basic entry", &ActivationType::Folder)?;
+    ///
+    /// for item in entry.walk().max_depth(1) {
+    ///     println!("{}", item?.path().join("/"));
+    /// }
+    /// ```
+    pub fn walk(&self) -> Walk {
+        Walk {
+            entry_type: self.entry_type.clone(),
+            stack: vec![Ok((self.name_path.clone(), 0))],
+            current: None,
+            max_depth: None,
+        }
+    }
+
+    /// Walks every entry of the given type. Root entries have a depth of 0.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// for item in CtxEntry::walk_type(&ActivationType::Folder) {
+    ///     println!("{}", item?.entry.address());
+    /// }
+    /// ```
+    pub fn walk_type(entry_type: &ActivationType) -> Walk {
+        Walk {
+            entry_type: entry_type.clone(),
+            stack: Vec::new(),
+            current: Some((Vec::new(), 0)),
+            max_depth: None,
+        }
+    }
+}
+
+impl Walk {
+    /// Stops the walk from descending deeper than the given depth.
+    pub fn max_depth(mut self, depth: usize) -> Walk {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Skips the children of the most recently yielded entry.
+    pub fn skip_children(&mut self) {
+        self.current = None;
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<WalkEntry>;
+
+    fn next(&mut self) -> Option<Result<WalkEntry>> {
+        if let Some((name_path, depth)) = self.current.take()
+            && self.max_depth.is_none_or(|max| depth <= max)
+        {
+            match read_child_names(&self.entry_type, &name_path) {
+                Ok(names) => {
+                    for name in names.into_iter().rev() {
+                        self.stack.push(name.map(|name| {
+                            let mut child_path = name_path.clone();
+                            child_path.push(name);
+                            (child_path, depth)
+                        }));
+                    }
+                }
+                Err(e) => self.stack.push(Err(e)),
+            }
+        }
+
+        let (name_path, depth) = match self.stack.pop()? {
+            Ok(item) => item,
+            Err(e) => return Some(Err(e)),
+        };
+
+        self.current = Some((name_path.clone(), depth + 1));
+
+        Some(Ok(WalkEntry {
+            entry: CtxEntry {
+                name_path,
+                entry_type: self.entry_type.clone(),
+                handle: None,
+            },
+            depth,
+        }))
+    }
+}
+
+// Reads the names of the entries under the given name path (or the root
+// entries if empty) in menu order. Names that fail to read are listed last.
+pub(crate) fn read_child_names(
+    entry_type: &ActivationType,
+    name_path: &[String],
+) -> Result<Vec<Result<String>>> {
+    let mut path = get_full_path(entry_type, name_path);
+
+    if !name_path.is_empty() {
+        path.push_str("\\shell");
+    }
+
+    let key = match hkcr()?.open_subkey(&path) {
+        Ok(key) => key,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("opening key", &path),
+    };
+
+    let (mut names, errors): (Vec<_>, Vec<_>) = key
+        .enum_keys()
+        .map(|name| name.map_err(|e| Error::from(e).with_context("listing keys", &path, None)))
+        .partition(|name| name.is_ok());
+    // Explorer orders static verbs by key name, ignoring case.
+    names.sort_by_key(|name| name.as_ref().ok().map(|n| n.to_lowercase()));
+    names.extend(errors);
+
+    Ok(names)
+}
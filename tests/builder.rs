@@ -0,0 +1,93 @@
+use common::{Sandbox, cleanup_entry};
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+#[test]
+fn build_nested_entries() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+
+    let built = CtxEntry::builder(&id, &ActivationType::Folder)
+        .icon("parent icon")
+        .position(MenuPosition::Top)
+        .child(|c| {
+            c.name("Terminal")
+                .command("cmd.exe")
+                .child(|c| c.name("Admin").label("As admin").extended(true))
+        })
+        .child(|c| c.name("Powershell").command("powershell.exe"))
+        .build()
+        .expect("Failed to build entries");
+
+    assert_eq!(built.entry.icon().unwrap().as_deref(), Some("parent icon"));
+    assert_eq!(built.entry.position().unwrap(), Some(MenuPosition::Top));
+    assert_eq!(built.children.len(), 2);
+
+    let terminal = &built.children[0];
+    assert_eq!(terminal.entry.name().unwrap(), "Terminal");
+    assert_eq!(
+        terminal.entry.command().unwrap().as_deref(),
+        Some("cmd.exe")
+    );
+
+    let admin = &terminal.children[0].entry;
+    assert_eq!(admin.name_path(), [id.as_str(), "Terminal", "Admin"]);
+    assert_eq!(admin.label().unwrap(), "As admin");
+    assert!(admin.extended().unwrap());
+
+    let powershell = &built.children[1].entry;
+    assert_eq!(
+        powershell.command().unwrap().as_deref(),
+        Some("powershell.exe")
+    );
+    cleanup_entry(built.entry);
+}
+
+#[test]
+fn build_validates_before_creating() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+
+    let e = CtxEntry::builder(&id, &ActivationType::Folder)
+        .child(|c| c.name("Child"))
+        .child(|c| c.name("Other").child(|c| c.name("a\\b")))
+        .build()
+        .unwrap_err();
+    assert!(matches!(e, Error::InvalidName { name, .. } if name == "a\\b"));
+
+    let e = CtxEntry::builder(&id, &ActivationType::Folder)
+        .child(|c| c.name("Child"))
+        .child(|c| c.name("child"))
+        .build()
+        .unwrap_err();
+    assert!(matches!(e, Error::InvalidName { .. }));
+
+    let e = CtxEntry::builder(&id, &ActivationType::Folder)
+        .child(|c| c)
+        .build()
+        .unwrap_err();
+    assert!(matches!(e, Error::InvalidName { .. }));
+
+    assert!(
+        CtxEntry::get(&[&id], &ActivationType::Folder)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn build_existing_entry() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+
+    let e = CtxEntry::builder(&id, &ActivationType::Folder)
+        .child(|c| c.name("Child"))
+        .build()
+        .unwrap_err();
+    assert!(matches!(e, Error::AlreadyExists { .. }));
+    assert!(entry.children().unwrap().is_empty());
+    cleanup_entry(entry);
+}
@@ -1,11 +1,5 @@
-use win_ctx::CtxEntry;
-use winreg::{RegKey, enums::HKEY_CLASSES_ROOT};
-
-pub fn cleanup_entry(entry: CtxEntry) {
-    let hkcr = RegKey::predef(HKEY_CLASSES_ROOT);
-    let path = entry.path();
-    hkcr.delete_subkey_all(&path).expect("Key cleanup failed");
-}
+// Tests run in a sandbox so they never touch real context menus.
+pub use win_ctx::testing::{Sandbox, cleanup_entry};
 
 #[macro_export]
 macro_rules! assert_reg_command {
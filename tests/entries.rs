@@ -2,60 +2,76 @@ use common::*;
 use std::io::ErrorKind;
 use uuid::Uuid;
 use win_ctx::*;
-use winreg::{RegKey, enums::*};
 
 mod common;
 
-const HKCR: RegKey = RegKey::predef(HKEY_CLASSES_ROOT);
-
 #[test]
 fn get_entry() {
+    let _sandbox = Sandbox::new().unwrap();
     let id = Uuid::new_v4().to_string();
     let new_entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
-    let entry = CtxEntry::get(&[&id], &ActivationType::Folder).unwrap();
+    let entry = CtxEntry::get(&[&id], &ActivationType::Folder)
+        .unwrap()
+        .unwrap();
     let name = entry.name().unwrap();
 
     assert_eq!(name, id);
     cleanup_entry(new_entry);
 }
 
-#[test]
-fn get_all_entries() {
-    let file_entries = CtxEntry::get_all_of_type(&ActivationType::File("*".to_string()));
-    let rs_ext_entries = CtxEntry::get_all_of_type(&ActivationType::File(".rs".to_string()));
-    let folder_entries = CtxEntry::get_all_of_type(&ActivationType::Folder);
-    let bg_entries = CtxEntry::get_all_of_type(&ActivationType::Background);
-
-    // No nice way to test these independently of all systems.
-    assert_eq!(file_entries.len(), 3);
-    assert_eq!(rs_ext_entries.len(), 0);
-    assert_eq!(folder_entries.len(), 4);
-    assert_eq!(bg_entries.len(), 3);
-}
-
 #[test]
 fn get_missing_entry() {
+    let _sandbox = Sandbox::new().unwrap();
     let id = Uuid::new_v4().to_string();
-    let entry = CtxEntry::get(&[id], &ActivationType::Background);
+    let entry = CtxEntry::get(&[id], &ActivationType::Background).unwrap();
 
     assert!(entry.is_none());
 }
 
 #[test]
 fn create_and_get_entry() {
+    let _sandbox = Sandbox::new().unwrap();
     let id = Uuid::new_v4().to_string();
     let new_entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
-    let get_entry = CtxEntry::get(&[id], &ActivationType::Folder).unwrap();
+    let get_entry = CtxEntry::get(&[id], &ActivationType::Folder)
+        .unwrap()
+        .unwrap();
 
-    assert_eq!(&new_entry.name_path, &get_entry.name_path);
+    assert_eq!(new_entry.name_path(), get_entry.name_path());
     cleanup_entry(new_entry);
 }
 
+#[test]
+fn find_entry_by_address() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry_type = ActivationType::File(".rs".to_string());
+    let parent = CtxEntry::new(&id, &entry_type).unwrap();
+    let child = parent.new_child("Format: all").unwrap();
+
+    let address = child.address().to_string();
+    assert_eq!(address, format!("file(.rs):{id}/Format\\: all"));
+
+    let found = CtxEntry::find(&address).unwrap().unwrap();
+    assert_eq!(found.name_path(), child.name_path());
+    assert_eq!(found.entry_type(), &entry_type);
+
+    let missing = CtxEntry::find(&format!("file(.rs):{id}/Missing")).unwrap();
+    assert!(missing.is_none());
+
+    let err = CtxEntry::find("desktop:Entry").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    cleanup_entry(parent);
+}
+
 #[test]
 fn basic_entry_on_all_files() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let entry = CtxEntry::new(&id, &ActivationType::File("*".to_string())).unwrap();
-    let key = HKCR.open_subkey(format!("*\\shell\\{id}")).unwrap();
+    let key = hkcr.open_subkey(format!("*\\shell\\{id}")).unwrap();
 
     assert_reg_command!(false, key);
     assert_reg_value!(false, key, "Icon");
@@ -66,9 +82,11 @@ fn basic_entry_on_all_files() {
 
 #[test]
 fn basic_entry_on_extension() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let entry = CtxEntry::new(&id, &ActivationType::File(".rs".to_string())).unwrap();
-    let key = HKCR.open_subkey(format!(".rs\\shell\\{id}")).unwrap();
+    let key = hkcr.open_subkey(format!(".rs\\shell\\{id}")).unwrap();
 
     assert_reg_command!(false, key);
     assert_reg_value!(false, key, "Icon");
@@ -79,9 +97,11 @@ fn basic_entry_on_extension() {
 
 #[test]
 fn basic_entry_on_folder() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
-    let key = HKCR.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
+    let key = hkcr.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
 
     assert_reg_command!(false, key);
     assert_reg_value!(false, key, "Icon");
@@ -92,9 +112,11 @@ fn basic_entry_on_folder() {
 
 #[test]
 fn basic_entry_on_background() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let entry = CtxEntry::new(&id, &ActivationType::Background).unwrap();
-    let key = HKCR
+    let key = hkcr
         .open_subkey(format!("Directory\\Background\\shell\\{id}"))
         .unwrap();
 
@@ -107,6 +129,8 @@ fn basic_entry_on_background() {
 
 #[test]
 fn entry_with_options() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let test_str = "test string";
     let entry = CtxEntry::new_with_options(
@@ -118,10 +142,11 @@ fn entry_with_options() {
             position: Some(MenuPosition::Top),
             separator: Some(Separator::After),
             extended: true,
+            ..Default::default()
         },
     )
     .unwrap();
-    let key = HKCR
+    let key = hkcr
         .open_subkey(format!("Directory\\Background\\shell\\{id}"))
         .unwrap();
 
@@ -134,10 +159,12 @@ fn entry_with_options() {
 
 #[test]
 fn get_and_set_command() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let command = "test command";
     let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
-    let key = HKCR.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
+    let key = hkcr.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
 
     assert_reg_command!(false, key);
     entry
@@ -150,10 +177,12 @@ fn get_and_set_command() {
 
 #[test]
 fn get_and_set_icon() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let icon = "test icon";
     let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
-    let key = HKCR.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
+    let key = hkcr.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
 
     assert_reg_value!(false, key, "Icon");
     entry.set_icon(Some(icon)).expect("Failed to set icon");
@@ -164,10 +193,12 @@ fn get_and_set_icon() {
 
 #[test]
 fn get_and_set_position() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let position = MenuPosition::Bottom;
     let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
-    let key = HKCR.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
+    let key = hkcr.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
 
     assert_reg_value!(false, key, "Position");
     entry
@@ -179,9 +210,11 @@ fn get_and_set_position() {
 
 #[test]
 fn get_and_set_separator() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
-    let key = HKCR.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
+    let key = hkcr.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
 
     assert_reg_value!(false, key, "SeparatorBefore");
     assert_reg_value!(false, key, "SeparatorAfter");
@@ -195,9 +228,11 @@ fn get_and_set_separator() {
 
 #[test]
 fn get_and_set_extended() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
-    let key = HKCR.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
+    let key = hkcr.open_subkey(format!("Directory\\shell\\{id}")).unwrap();
 
     assert_reg_value!(false, key, "Extended");
     entry.set_extended(true).expect("Failed to set extended");
@@ -208,14 +243,16 @@ fn get_and_set_extended() {
 
 #[test]
 fn rename_entry() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let old_id = Uuid::new_v4().to_string();
     let new_id = Uuid::new_v4().to_string();
     let mut entry = CtxEntry::new(&old_id, &ActivationType::Folder).unwrap();
 
-    HKCR.open_subkey(format!("Directory\\shell\\{}", old_id))
+    hkcr.open_subkey(format!("Directory\\shell\\{}", old_id))
         .expect("Initial entry does not exist");
     entry.rename(&new_id).expect("Failed to rename entry");
-    HKCR.open_subkey(format!("Directory\\shell\\{}", new_id))
+    hkcr.open_subkey(format!("Directory\\shell\\{}", new_id))
         .expect("Renamed entry does not exist");
 
     entry
@@ -227,10 +264,189 @@ fn rename_entry() {
 
 #[test]
 fn delete_entry() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
     let id = Uuid::new_v4().to_string();
     let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
 
     entry.delete().expect("Failed to delete entry");
-    HKCR.open_subkey(format!("Directory\\shell\\{id}"))
+    hkcr.open_subkey(format!("Directory\\shell\\{id}"))
         .expect_err("Found key after deletion");
 }
+
+#[test]
+fn rename_child_entry() {
+    let _sandbox = Sandbox::new().unwrap();
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    let mut child = parent.new_child("Old name").unwrap();
+
+    child.rename("New name").expect("Failed to rename child");
+
+    assert_eq!(child.name().unwrap(), "New name");
+    assert!(parent.child("New name").unwrap().is_some());
+    assert!(parent.child("Old name").unwrap().is_none());
+    cleanup_entry(parent);
+}
+
+#[test]
+fn rename_invalid_names() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+
+    for name in ["a\\b", "open", "RunAs", &"x".repeat(256)] {
+        let err = entry
+            .rename(name)
+            .expect_err("Invalid name should not be allowed");
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    assert_eq!(entry.name().unwrap(), id);
+    cleanup_entry(entry);
+}
+
+#[test]
+fn rename_conflict_policies() {
+    let _sandbox = Sandbox::new().unwrap();
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    let mut source = parent.new_child("Source").unwrap();
+    source.set_command(Some("source command")).unwrap();
+    let mut target = parent.new_child("Target").unwrap();
+    target.set_icon(Some("target icon")).unwrap();
+
+    let err = source
+        .rename("Target")
+        .expect_err("Conflicting rename should fail");
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert_eq!(
+        source.name().unwrap(),
+        "Source",
+        "Failed rename changed entry"
+    );
+
+    source
+        .rename_with_options(
+            "Target",
+            &RenameOptions {
+                conflict: ConflictPolicy::Merge,
+                update_label: true,
+            },
+        )
+        .expect("Failed to merge entry");
+
+    assert_eq!(parent.children().unwrap().len(), 1);
+    assert_eq!(source.command().unwrap().as_deref(), Some("source command"));
+    assert_eq!(source.icon().unwrap().as_deref(), Some("target icon"));
+    assert_eq!(source.label().unwrap(), "Target");
+
+    // A failed overwrite keeps the existing entry.
+    let mut other = parent.new_child("Other").unwrap();
+    let blocker = parent.new_child("~backup-Target").unwrap();
+    let overwrite = RenameOptions {
+        conflict: ConflictPolicy::Overwrite,
+        update_label: false,
+    };
+    other
+        .rename_with_options("Target", &overwrite)
+        .expect_err("Clashing backup name should fail");
+    assert_eq!(other.name().unwrap(), "Other");
+    assert_eq!(source.icon().unwrap().as_deref(), Some("target icon"));
+    cleanup_entry(blocker);
+
+    other
+        .rename_with_options("Target", &overwrite)
+        .expect("Failed to overwrite entry");
+
+    assert_eq!(parent.children().unwrap().len(), 1);
+    assert_eq!(other.command().unwrap(), None);
+    cleanup_entry(parent);
+}
+
+#[test]
+fn error_context() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let stale = CtxEntry::get(&[&id], &ActivationType::Folder)
+        .unwrap()
+        .unwrap();
+    entry.delete().unwrap();
+
+    let err = stale.icon().unwrap_err();
+    assert!(matches!(err, Error::NotFound { .. }));
+    assert_eq!(err.context().operation, Some("opening key"));
+    assert_eq!(err.context().path, Some(format!("Directory\\shell\\{id}")));
+
+    let err = std::io::Error::from(stale.command().unwrap_err());
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn read_all_properties() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new_with_options(
+        &id,
+        &ActivationType::Folder,
+        &EntryOptions {
+            command: Some("test command".to_string()),
+            icon: Some("test icon".to_string()),
+            position: Some(MenuPosition::Bottom),
+            separator: Some(Separator::Before),
+            extended: true,
+            owner: Some("test owner".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    entry.set_label(Some("Test label")).unwrap();
+
+    let props = entry.properties().expect("Failed to read properties");
+    assert_eq!(props.name, id);
+    assert_eq!(props.display_label(), "Test label");
+    assert_eq!(props.command.as_deref(), Some("test command"));
+    assert_eq!(props.icon.as_deref(), Some("test icon"));
+    assert_eq!(props.position, Some(MenuPosition::Bottom));
+    assert_eq!(props.separator, Some(Separator::Before));
+    assert!(props.extended);
+    assert_eq!(props.owner.as_deref(), Some("test owner"));
+
+    entry.set_label(None).unwrap();
+    assert_eq!(entry.properties().unwrap().display_label(), id);
+    cleanup_entry(entry);
+}
+
+#[test]
+fn open_and_close_handle() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    entry.new_child("Child").unwrap();
+
+    entry
+        .open_handle(Access::Read)
+        .expect("Failed to open handle");
+    assert_eq!(entry.name().unwrap(), id);
+    assert_eq!(entry.children().unwrap().len(), 1);
+
+    // Writes still work with a read-only handle.
+    entry.set_icon(Some("test icon")).unwrap();
+    assert_eq!(entry.icon().unwrap().as_deref(), Some("test icon"));
+
+    entry.open_handle(Access::ReadWrite).unwrap();
+    entry.set_command(Some("test command")).unwrap();
+    assert_eq!(entry.command().unwrap().as_deref(), Some("test command"));
+
+    entry.rename(&format!("{id}-renamed")).unwrap();
+    assert_eq!(entry.icon().unwrap().as_deref(), Some("test icon"));
+    entry.close_handle();
+    cleanup_entry(entry);
+
+    let mut missing = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    hkcr.delete_subkey_all(missing.path()).unwrap();
+    let err = missing.open_handle(Access::Read).unwrap_err();
+    assert!(matches!(err, Error::NotFound { .. }));
+}
@@ -0,0 +1,98 @@
+use common::{Sandbox, cleanup_entry};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(l, t)| (l.to_string(), t.to_string()))
+        .collect()
+}
+
+#[test]
+fn set_and_apply_localized_labels() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let table = labels(&[("de-DE", "Öffnen in"), ("*", "@tool.dll,-101")]);
+
+    entry
+        .set_localized_labels(&table)
+        .expect("Failed to set localized labels");
+    assert_eq!(entry.localized_labels().unwrap(), table);
+    assert!(table.values().any(|l| *l == entry.label().unwrap()));
+
+    entry.apply_localized_label(&["de-AT"]).unwrap();
+    assert_eq!(entry.label().unwrap(), "Öffnen in");
+
+    entry.apply_localized_label(&["fr-FR"]).unwrap();
+    assert_eq!(entry.label().unwrap(), "@tool.dll,-101");
+
+    entry.set_localized_labels(&BTreeMap::new()).unwrap();
+    assert!(entry.localized_labels().unwrap().is_empty());
+    assert_eq!(entry.label().unwrap(), id);
+    cleanup_entry(entry);
+}
+
+#[test]
+fn localized_labels_from_options() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let languages = ui_languages().expect("Failed to get UI languages");
+    let table = labels(&[(&languages[0], "Localized")]);
+
+    let entry = CtxEntry::new_with_options(
+        &id,
+        &ActivationType::Folder,
+        &EntryOptions {
+            labels: table.clone(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(entry.label().unwrap(), "Localized");
+
+    let built = CtxEntry::builder(&format!("{id}-built"), &ActivationType::Folder)
+        .child(|c| {
+            c.name("Child")
+                .localized_label(&languages[0], "Localized child")
+        })
+        .build()
+        .unwrap();
+    assert_eq!(built.children[0].entry.label().unwrap(), "Localized child");
+    cleanup_entry(entry);
+    cleanup_entry(built.entry);
+}
+
+#[test]
+fn invalid_label_language() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+
+    let e = entry
+        .set_localized_labels(&labels(&[("a=b", "Label")]))
+        .unwrap_err();
+    assert!(matches!(e, Error::InvalidValue { .. }));
+    cleanup_entry(entry);
+}
+
+#[test]
+fn refresh_localized_labels() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let languages = ui_languages().unwrap();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    entry
+        .set_localized_labels(&labels(&[(&languages[0], "Localized")]))
+        .unwrap();
+    entry.set_label(Some("Stale")).unwrap();
+
+    let updated = CtxEntry::refresh_localized_labels().expect("Failed to refresh labels");
+    assert!(updated.iter().any(|e| e.path() == entry.path()));
+    assert_eq!(entry.label().unwrap(), "Localized");
+    cleanup_entry(entry);
+}
@@ -0,0 +1,72 @@
+use common::{Sandbox, cleanup_entry};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+#[derive(Default)]
+struct RecordingNotifier {
+    count: AtomicUsize,
+}
+
+impl Notifier for RecordingNotifier {
+    fn notify(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl RecordingNotifier {
+    fn take(&self) -> usize {
+        self.count.swap(0, Ordering::SeqCst)
+    }
+}
+
+// Kept as a single test since the notifier is global.
+#[test]
+fn notify_after_changes() {
+    let _sandbox = Sandbox::new().unwrap();
+    let notifier = Arc::new(RecordingNotifier::default());
+    set_notifier(Some(notifier.clone()));
+
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new_with_options(
+        &id,
+        &ActivationType::Folder,
+        &EntryOptions {
+            command: Some("cmd.exe".to_string()),
+            icon: Some("cmd.exe".to_string()),
+            position: None,
+            separator: None,
+            extended: true,
+            owner: None,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(notifier.take(), 1, "Creating an entry should notify once");
+
+    entry.set_icon(None).unwrap();
+    assert_eq!(notifier.take(), 1);
+
+    entry.icon().unwrap();
+    entry.children().unwrap();
+    assert_eq!(notifier.take(), 0, "Reads should not notify");
+
+    batch(|| {
+        entry.new_child("Child 1").unwrap();
+        entry.new_child("Child 2").unwrap();
+        entry.set_extended(false).unwrap();
+    });
+    assert_eq!(notifier.take(), 1, "A batch should notify once");
+
+    batch(|| entry.name().unwrap());
+    assert_eq!(notifier.take(), 0, "An empty batch should not notify");
+
+    set_notifier(None);
+    entry.set_icon(Some("cmd.exe")).unwrap();
+    assert_eq!(notifier.take(), 0);
+
+    cleanup_entry(entry);
+}
@@ -0,0 +1,93 @@
+use common::{Sandbox, cleanup_entry};
+use uuid::Uuid;
+use win_ctx::*;
+use winreg::{RegKey, enums::*};
+
+mod common;
+
+const HKCU: RegKey = RegKey::predef(HKEY_CURRENT_USER);
+
+fn entry_with_command(
+    parent: Option<&CtxEntry>,
+    name: &str,
+    command: &str,
+    entry_type: &ActivationType,
+) -> CtxEntry {
+    let opts = EntryOptions {
+        command: Some(command.to_string()),
+        ..Default::default()
+    };
+
+    match parent {
+        Some(parent) => parent.new_child_with_options(name, &opts).unwrap(),
+        None => CtxEntry::new_with_options(name, entry_type, &opts).unwrap(),
+    }
+}
+
+#[test]
+fn find_and_clean_orphans() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry_type = ActivationType::File(format!(".{id}"));
+    let exe = std::env::current_exe().unwrap();
+    let exe = exe.to_str().unwrap();
+
+    let app_path = format!(r"Software\Microsoft\Windows\CurrentVersion\App Paths\{id}.exe");
+    let (key, _) = HKCU.create_subkey(&app_path).unwrap();
+    key.set_value("", &exe).unwrap();
+
+    let missing = entry_with_command(
+        None,
+        "Missing",
+        r#""C:\missing\tool.exe" "%1""#,
+        &entry_type,
+    );
+    let present = entry_with_command(None, "Present", &format!("\"{exe}\" \"%1\""), &entry_type);
+    let registered = entry_with_command(None, "Registered", &format!("{id} \"%1\""), &entry_type);
+    let placeholder = entry_with_command(None, "Placeholder", r#""%1""#, &entry_type);
+    let excluded = entry_with_command(None, "Excluded", r"C:\missing\tool.exe %1", &entry_type);
+    let mut disabled = entry_with_command(None, "Disabled", r"C:\missing\tool.exe %1", &entry_type);
+    disabled.set_disabled(true).unwrap();
+    assert!(disabled.disabled().unwrap());
+
+    let cascade = CtxEntry::new("Cascade", &entry_type).unwrap();
+    entry_with_command(Some(&cascade), "Gone", r"C:\missing\gone.exe", &entry_type);
+
+    let scan = OrphanScan::new()
+        .entry_type(&entry_type)
+        .exclude("*:exclu*");
+    let orphans = scan.run().unwrap();
+
+    assert_eq!(
+        orphan_report(&orphans),
+        format!(
+            "file(.{id}):Cascade/Gone: \"C:\\\\missing\\\\gone.exe\" not found\n\
+             file(.{id}):Missing: \"C:\\\\missing\\\\tool.exe\" not found\n"
+        )
+    );
+    assert_eq!(orphans[1].command, r#""C:\missing\tool.exe" "%1""#);
+
+    let mut orphans = orphans.into_iter();
+    orphans.next().unwrap().remove().unwrap();
+    orphans.next().unwrap().disable().unwrap();
+
+    assert!(cascade.children().unwrap().is_empty());
+    assert_eq!(cascade.get_value("Subcommands").unwrap(), None);
+    assert!(missing.disabled().unwrap());
+    assert!(scan.run().unwrap().is_empty());
+
+    for entry in [
+        missing,
+        present,
+        registered,
+        placeholder,
+        excluded,
+        disabled,
+        cascade,
+    ] {
+        cleanup_entry(entry);
+    }
+    hkcr.delete_subkey_all(format!(".{id}")).unwrap();
+    HKCU.delete_subkey_all(&app_path).unwrap();
+}
@@ -0,0 +1,113 @@
+use common::{Sandbox, cleanup_entry};
+use std::io::ErrorKind;
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+fn owned_options(owner: &str) -> EntryOptions {
+    EntryOptions {
+        command: None,
+        icon: None,
+        position: None,
+        separator: None,
+        extended: false,
+        owner: Some(owner.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn stamp_owner() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let owner = Uuid::new_v4().to_string();
+    let mut entry =
+        CtxEntry::new_with_options(&id, &ActivationType::Folder, &owned_options(&owner)).unwrap();
+
+    assert_eq!(entry.owner().unwrap(), Some(owner));
+    entry.set_owner(None).expect("Failed to clear owner");
+    assert_eq!(entry.owner().unwrap(), None);
+    cleanup_entry(entry);
+}
+
+#[test]
+fn get_entries_by_owner() {
+    let _sandbox = Sandbox::new().unwrap();
+    let owner = Uuid::new_v4().to_string();
+    let unowned = CtxEntry::new(&Uuid::new_v4().to_string(), &ActivationType::Folder).unwrap();
+    let owned_child = unowned
+        .new_child_with_options("Owned child", &owned_options(&owner))
+        .unwrap();
+    let owned_root = CtxEntry::new_with_options(
+        &Uuid::new_v4().to_string(),
+        &ActivationType::File(".rs".to_string()),
+        &owned_options(&owner),
+    )
+    .unwrap();
+    owned_root
+        .new_child_with_options("Nested", &owned_options(&owner))
+        .unwrap();
+
+    let mut paths: Vec<String> = CtxEntry::get_all_by_owner(&owner)
+        .unwrap()
+        .iter()
+        .map(|e| e.path())
+        .collect();
+    paths.sort();
+    let mut expected = vec![owned_child.path(), owned_root.path()];
+    expected.sort();
+
+    assert_eq!(paths, expected);
+    cleanup_entry(unowned);
+    cleanup_entry(owned_root);
+}
+
+#[test]
+fn purge_owned_entries() {
+    let _sandbox = Sandbox::new().unwrap();
+    let owner = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&Uuid::new_v4().to_string(), &ActivationType::Background).unwrap();
+    let child = parent
+        .new_child_with_options("Owned child", &owned_options(&owner))
+        .unwrap();
+    let root = CtxEntry::new_with_options(
+        &Uuid::new_v4().to_string(),
+        &ActivationType::Background,
+        &owned_options(&owner),
+    )
+    .unwrap();
+    root.new_child("Foreign child").unwrap();
+
+    let err = CtxEntry::purge_owner(&owner, false)
+        .expect_err("Purge should refuse to delete unowned children");
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert!(child.name().is_ok(), "Nothing should be deleted on refusal");
+
+    let deleted = CtxEntry::purge_owner(&owner, true).expect("Forced purge failed");
+
+    assert_eq!(deleted.len(), 2);
+    assert!(child.name().is_err(), "Owned child should be deleted");
+    assert!(root.name().is_err(), "Owned root should be deleted");
+    assert!(parent.children().unwrap().is_empty());
+    cleanup_entry(parent);
+}
+
+#[test]
+fn purge_keeps_foreign_parent() {
+    let _sandbox = Sandbox::new().unwrap();
+    let owner = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&Uuid::new_v4().to_string(), &ActivationType::Folder).unwrap();
+    let child = parent
+        .new_child_with_options("Owned child", &owned_options(&owner))
+        .unwrap();
+
+    CtxEntry::purge_owner(&owner, false).expect("Purge failed");
+
+    assert!(child.name().is_err(), "Owned child should be deleted");
+    assert!(
+        parent.get_value("Subcommands").unwrap().is_some(),
+        "Unowned parent should keep its Subcommands value"
+    );
+    cleanup_entry(parent);
+}
@@ -0,0 +1,82 @@
+use common::{Sandbox, cleanup_entry};
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+#[test]
+fn query_nested_commands() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let marker = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Background).unwrap();
+    let mut child = entry.new_child("Editor").unwrap();
+    child
+        .set_command(Some(&format!("code.exe --marker {marker} \"%V\"")))
+        .unwrap();
+
+    let matches = Query::new().command(&marker.to_uppercase()).run().unwrap();
+
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].entry.name_path(), child.name_path());
+    assert_eq!(matches[0].depth, 1);
+    assert_eq!(
+        matches[0].address.to_string(),
+        format!("background:{id}/Editor")
+    );
+
+    let shallow = Query::new().command(&marker).max_depth(0).run().unwrap();
+    assert!(shallow.is_empty());
+
+    cleanup_entry(entry);
+}
+
+#[test]
+fn query_name_and_flags() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let mut shift_only = entry.new_child("Shift only").unwrap();
+    shift_only.set_extended(true).unwrap();
+    entry.new_child("Always shown").unwrap();
+
+    let matches = Query::new()
+        .entry_type(&ActivationType::Folder)
+        .name(&format!("{id}*"))
+        .run()
+        .unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].entry.name_path(), entry.name_path());
+
+    let matches = Query::new()
+        .entry_type(&ActivationType::Folder)
+        .label("*only")
+        .extended(true)
+        .min_depth(1)
+        .run()
+        .unwrap();
+    let paths: Vec<_> = matches.iter().map(|m| m.entry.name_path()).collect();
+    assert!(paths.contains(&shift_only.name_path()));
+    assert!(matches.iter().all(|m| m.entry.extended().unwrap()));
+
+    cleanup_entry(entry);
+}
+
+#[test]
+fn query_results_in_menu_order() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let owner = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Background).unwrap();
+
+    for name in ["b", "C", "a"] {
+        let mut child = entry.new_child(name).unwrap();
+        child.set_owner(Some(&owner)).unwrap();
+    }
+
+    let matches = Query::new().owner(&owner).run().unwrap();
+    let names: Vec<_> = matches.iter().map(|m| m.entry.name().unwrap()).collect();
+
+    assert_eq!(names, ["a", "b", "C"]);
+    cleanup_entry(entry);
+}
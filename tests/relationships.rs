@@ -1,4 +1,5 @@
-use common::cleanup_entry;
+use common::{Sandbox, cleanup_entry};
+use std::io::ErrorKind;
 use uuid::Uuid;
 use win_ctx::*;
 
@@ -6,15 +7,17 @@ mod common;
 
 #[test]
 fn root_has_no_parent() {
+    let _sandbox = Sandbox::new().unwrap();
     let id = Uuid::new_v4().to_string();
     let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
 
-    assert!(entry.parent().is_none());
+    assert!(entry.parent().unwrap().is_none());
     cleanup_entry(entry);
 }
 
 #[test]
 fn new_entry_has_no_children() {
+    let _sandbox = Sandbox::new().unwrap();
     let id = Uuid::new_v4().to_string();
     let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
 
@@ -24,6 +27,7 @@ fn new_entry_has_no_children() {
 
 #[test]
 fn parent_with_children() {
+    let _sandbox = Sandbox::new().unwrap();
     let parent_id = Uuid::new_v4().to_string();
     let child_1_id = Uuid::new_v4().to_string();
     let child_2_id = Uuid::new_v4().to_string();
@@ -44,11 +48,17 @@ fn parent_with_children() {
         parent.child(&child_2_id).unwrap().is_some(),
         "Parent should have child 2"
     );
-    assert!(child_1.parent().is_some(), "Child 1 should have parent");
-    assert!(child_2.parent().is_some(), "Child 2 should have parent");
+    assert!(
+        child_1.parent().unwrap().is_some(),
+        "Child 1 should have parent"
+    );
+    assert!(
+        child_2.parent().unwrap().is_some(),
+        "Child 2 should have parent"
+    );
     assert_eq!(
-        child_1.parent().unwrap().path(),
-        child_2.parent().unwrap().path(),
+        child_1.parent().unwrap().unwrap().path(),
+        child_2.parent().unwrap().unwrap().path(),
         "Children do not have same parent"
     );
     cleanup_entry(parent);
@@ -56,17 +66,22 @@ fn parent_with_children() {
 
 #[test]
 fn orphan_basic() {
+    let _sandbox = Sandbox::new().unwrap();
     let parent_id = Uuid::new_v4().to_string();
     let child_id = Uuid::new_v4().to_string();
     let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
     let child = parent.new_child(&child_id).unwrap();
 
     parent.delete().expect("Failed to delete parent");
-    assert!(child.parent().is_none(), "Child has parent after deletion");
+    assert!(
+        child.parent().unwrap().is_none(),
+        "Child has parent after deletion"
+    );
 }
 
 #[test]
 fn orphan_with_error_value() {
+    let _sandbox = Sandbox::new().unwrap();
     let parent_id = Uuid::new_v4().to_string();
     let child_id = Uuid::new_v4().to_string();
     let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
@@ -80,3 +95,138 @@ fn orphan_with_error_value() {
         .icon()
         .expect_err("Should not be able to get child icon after orphaned");
 }
+
+#[test]
+fn children_in_menu_order() {
+    let _sandbox = Sandbox::new().unwrap();
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    parent.new_child("b").unwrap();
+    parent.new_child("C").unwrap();
+    parent.new_child("a").unwrap();
+
+    let names: Vec<String> = parent
+        .children()
+        .unwrap()
+        .iter()
+        .map(|c| c.name().unwrap())
+        .collect();
+
+    assert_eq!(names, ["a", "b", "C"]);
+    cleanup_entry(parent);
+}
+
+#[test]
+fn reorder_children() {
+    let _sandbox = Sandbox::new().unwrap();
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    parent.new_child("Alpha").unwrap();
+    parent.new_child("Beta").unwrap();
+    parent.new_child("Gamma").unwrap();
+
+    let reordered = parent.reorder_children(&["Gamma", "Alpha"]).unwrap();
+    let labels: Vec<String> = parent
+        .children()
+        .unwrap()
+        .iter()
+        .map(|c| c.label().unwrap())
+        .collect();
+
+    assert_eq!(labels, ["Gamma", "Alpha", "Beta"]);
+    assert_eq!(reordered[0].name().unwrap(), "00_Gamma");
+    assert_eq!(reordered[2].name().unwrap(), "02_Beta");
+
+    let moved = parent.move_child_before("Beta", "Gamma").unwrap();
+    let labels: Vec<String> = moved.iter().map(|c| c.label().unwrap()).collect();
+
+    assert_eq!(labels, ["Beta", "Gamma", "Alpha"]);
+    assert_eq!(moved[0].name().unwrap(), "00_Beta");
+
+    // Children already in place keep their names.
+    let again = parent.reorder_children(&["Beta", "Gamma"]).unwrap();
+    let names: Vec<String> = again.iter().map(|c| c.name().unwrap()).collect();
+    assert_eq!(names, ["00_Beta", "01_Gamma", "02_Alpha"]);
+
+    parent
+        .reorder_children(&["Missing"])
+        .expect_err("Unknown labels should not be allowed");
+    cleanup_entry(parent);
+}
+
+#[test]
+fn reorder_children_keeps_numbered_names() {
+    let _sandbox = Sandbox::new().unwrap();
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    parent.new_child("Alpha").unwrap();
+    let mut report = parent.new_child("2023_report").unwrap();
+    report.set_label(Some("Report")).unwrap();
+
+    let reordered = parent.reorder_children(&["Alpha", "Report"]).unwrap();
+    let names: Vec<String> = reordered.iter().map(|c| c.name().unwrap()).collect();
+
+    assert_eq!(names, ["00_Alpha", "01_2023_report"]);
+    assert_eq!(reordered[1].label().unwrap(), "Report");
+    cleanup_entry(parent);
+}
+
+#[test]
+fn reorder_children_rolls_back() {
+    let _sandbox = Sandbox::new().unwrap();
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    parent.new_child("Alpha").unwrap();
+    // Too long for a key name once prefixed, so renaming it fails after
+    // the first child has been renamed.
+    let long_name = "B".repeat(253);
+    parent.new_child(&long_name).unwrap();
+
+    parent
+        .reorder_children(&["Alpha", long_name.as_str()])
+        .expect_err("Overlong name should fail");
+
+    let children = parent.children().unwrap();
+    let names: Vec<String> = children.iter().map(|c| c.name().unwrap()).collect();
+
+    assert_eq!(names, ["Alpha".to_string(), long_name]);
+    assert!(
+        children
+            .iter()
+            .all(|c| c.get_value("MUIVerb").unwrap().is_none())
+    );
+    cleanup_entry(parent);
+}
+
+#[test]
+fn reorder_children_avoids_taken_names() {
+    let _sandbox = Sandbox::new().unwrap();
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    parent.new_child("Alpha").unwrap();
+    parent.new_child("Beta").unwrap();
+    parent.new_child("~reorder1").unwrap();
+
+    let reordered = parent.reorder_children(&["Beta", "Alpha"]).unwrap();
+    let names: Vec<String> = reordered.iter().map(|c| c.name().unwrap()).collect();
+
+    assert_eq!(names, ["00_Beta", "01_Alpha", "02_~reorder1"]);
+    cleanup_entry(parent);
+}
+
+#[test]
+fn reorder_children_duplicate_labels() {
+    let _sandbox = Sandbox::new().unwrap();
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    parent.new_child("Alpha").unwrap();
+    let mut other = parent.new_child("Other").unwrap();
+    other.set_label(Some("Alpha")).unwrap();
+
+    let err = parent
+        .reorder_children(&["Alpha"])
+        .expect_err("Ambiguous labels should not be allowed");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(parent.children().unwrap()[1].name().unwrap(), "Other");
+    cleanup_entry(parent);
+}
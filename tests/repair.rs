@@ -0,0 +1,180 @@
+use common::{Sandbox, cleanup_entry};
+use uuid::Uuid;
+use win_ctx::*;
+use winreg::enums::*;
+
+mod common;
+
+fn kinds(repairs: &[Repair]) -> Vec<(RepairKind, String)> {
+    repairs
+        .iter()
+        .map(|r| (r.kind, r.address.name_path.join("/")))
+        .collect()
+}
+
+#[test]
+fn repair_consistent_entry() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let child = entry.new_child("Child").unwrap();
+    let mut grandchild = child.new_child("Grandchild").unwrap();
+    grandchild.set_command(Some("cmd.exe")).unwrap();
+
+    assert_eq!(entry.repair(false).unwrap(), []);
+    cleanup_entry(entry);
+}
+
+#[test]
+fn repair_subcommands() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let child = entry.new_child("Child").unwrap();
+    child.new_child("Grandchild").unwrap();
+
+    // Lose the parent's value and leave the child with no children.
+    let key = hkcr
+        .open_subkey_with_flags(entry.path(), KEY_ALL_ACCESS)
+        .unwrap();
+    key.delete_value("Subcommands").unwrap();
+    hkcr.delete_subkey(format!("{}\\shell\\Grandchild", child.path()))
+        .unwrap();
+
+    let expected = [
+        (RepairKind::MissingSubcommands, id.clone()),
+        (RepairKind::StaleSubcommands, format!("{id}/Child")),
+    ];
+
+    let planned = entry.repair(true).unwrap();
+    assert_eq!(kinds(&planned), expected);
+    assert_eq!(entry.get_value("Subcommands").unwrap(), None);
+
+    let applied = entry.repair(false).unwrap();
+    assert_eq!(applied, planned);
+    assert!(entry.get_value("Subcommands").unwrap().is_some());
+    assert_eq!(child.get_value("Subcommands").unwrap(), None);
+    assert!(
+        hkcr.open_subkey(format!("{}\\shell", child.path()))
+            .is_err()
+    );
+
+    assert_eq!(entry.repair(false).unwrap(), []);
+    cleanup_entry(entry);
+}
+
+#[test]
+fn repair_extended_subcommands() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let tools = RegValue::Sz("Directory\\ContextMenus\\Tools".to_string());
+    entry
+        .set_value("Subcommands", &RegValue::Sz(String::new()))
+        .unwrap();
+    entry.set_value("ExtendedSubCommandsKey", &tools).unwrap();
+
+    assert_eq!(entry.repair(false).unwrap(), []);
+    assert!(entry.get_value("Subcommands").unwrap().is_some());
+    assert_eq!(
+        entry.get_value("ExtendedSubCommandsKey").unwrap(),
+        Some(tools)
+    );
+    cleanup_entry(entry);
+}
+
+#[test]
+fn repair_commands() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let mut empty = entry.new_child("Empty").unwrap();
+    empty.set_command(Some("")).unwrap();
+    let mut delegate = entry.new_child("Delegate").unwrap();
+    delegate.set_command(Some("")).unwrap();
+    let (command_key, _) = hkcr
+        .create_subkey(format!("{}\\command", delegate.path()))
+        .unwrap();
+    command_key
+        .set_value("DelegateExecute", &"{00000000-0000-0000-0000-000000000000}")
+        .unwrap();
+
+    let mut hidden = entry.new_child("Hidden").unwrap();
+    hidden.set_command(Some("cmd.exe")).unwrap();
+    hidden.new_child("Unreachable").unwrap();
+    hkcr.open_subkey_with_flags(hidden.path(), KEY_ALL_ACCESS)
+        .unwrap()
+        .delete_value("Subcommands")
+        .unwrap();
+
+    let repairs = entry.repair(false).unwrap();
+    assert_eq!(
+        kinds(&repairs),
+        [
+            (RepairKind::EmptyCommand, format!("{id}/Empty")),
+            (RepairKind::UnreachableChildren, format!("{id}/Hidden")),
+        ]
+    );
+    assert_eq!(
+        repair_report(&repairs),
+        format!(
+            "folder:{id}/Empty: remove empty `command` key\n\
+             folder:{id}/Hidden: children hidden by the command (needs manual action)\n"
+        )
+    );
+    assert!(!repairs[0].manual);
+    assert!(repairs[1].manual);
+
+    assert!(
+        hkcr.open_subkey(format!("{}\\command", empty.path()))
+            .is_err()
+    );
+    assert!(
+        hkcr.open_subkey(format!("{}\\command", delegate.path()))
+            .is_ok()
+    );
+    assert_eq!(hidden.children().unwrap().len(), 1);
+    assert_eq!(hidden.get_value("Subcommands").unwrap(), None);
+
+    let opts = RepairOptions {
+        show_hidden_children: true,
+        ..Default::default()
+    };
+    let repairs = entry.repair_with_options(&opts).unwrap();
+    assert_eq!(
+        kinds(&repairs),
+        [(RepairKind::UnreachableChildren, format!("{id}/Hidden"))]
+    );
+    assert!(!repairs[0].manual);
+    assert!(hidden.get_value("Subcommands").unwrap().is_some());
+    assert_eq!(hidden.children().unwrap().len(), 1);
+    assert_eq!(hidden.command().unwrap().as_deref(), Some("cmd.exe"));
+    assert_eq!(entry.repair_with_options(&opts).unwrap(), []);
+
+    cleanup_entry(entry);
+}
+
+#[test]
+fn repair_type() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry_type = ActivationType::File(format!(".{id}"));
+    let entry = CtxEntry::new("Entry", &entry_type).unwrap();
+    entry.new_child("Child").unwrap();
+    entry.child("Child").unwrap().unwrap().delete().unwrap();
+
+    let planned = CtxEntry::repair_type(&entry_type, true).unwrap();
+    assert_eq!(
+        kinds(&planned),
+        [(RepairKind::StaleSubcommands, "Entry".to_string())]
+    );
+
+    CtxEntry::repair_type(&entry_type, false).unwrap();
+    assert_eq!(CtxEntry::repair_type(&entry_type, true).unwrap(), []);
+
+    cleanup_entry(entry);
+    hkcr.delete_subkey_all(format!(".{id}")).unwrap();
+}
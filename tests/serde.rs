@@ -0,0 +1,59 @@
+#![cfg(feature = "serde")]
+
+use win_ctx::*;
+
+#[test]
+fn activation_type_representation() {
+    let entry_types = [
+        (ActivationType::File(".rs".to_string()), "\"file:.rs\""),
+        (ActivationType::File("*".to_string()), "\"file:*\""),
+        (ActivationType::Folder, "\"folder\""),
+        (ActivationType::Background, "\"background\""),
+    ];
+
+    for (entry_type, json) in entry_types {
+        assert_eq!(serde_json::to_string(&entry_type).unwrap(), json);
+        assert_eq!(
+            serde_json::from_str::<ActivationType>(json).unwrap(),
+            entry_type
+        );
+    }
+
+    let entry_type: ActivationType = serde_json::from_str("\"file(.rs)\"").unwrap();
+    assert_eq!(entry_type, ActivationType::File(".rs".to_string()));
+
+    serde_json::from_str::<ActivationType>("\"file:\"").unwrap_err();
+    serde_json::from_str::<ActivationType>("\"desktop\"").unwrap_err();
+}
+
+#[test]
+fn entry_options_representation() {
+    let opts = EntryOptions {
+        command: Some("cmd.exe".to_string()),
+        position: Some(MenuPosition::Top),
+        separator: Some(Separator::Both),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_value(&opts).unwrap();
+    assert_eq!(json["position"], "top");
+    assert_eq!(json["separator"], "both");
+
+    let opts: EntryOptions =
+        serde_json::from_str(r#"{ "command": "cmd.exe", "extended": true }"#).unwrap();
+    assert_eq!(opts.command.as_deref(), Some("cmd.exe"));
+    assert!(opts.extended);
+    assert!(opts.icon.is_none());
+    assert!(opts.position.is_none());
+}
+
+#[test]
+fn entry_address_representation() {
+    let address: EntryAddress = "file(.rs):Open in/Terminal".parse().unwrap();
+    let json = serde_json::to_string(&address).unwrap();
+    assert_eq!(json, "\"file(.rs):Open in/Terminal\"");
+    assert_eq!(
+        serde_json::from_str::<EntryAddress>(&json).unwrap(),
+        address
+    );
+}
@@ -0,0 +1,206 @@
+use common::{Sandbox, cleanup_entry};
+use std::io::ErrorKind;
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+#[test]
+fn snapshot_entry_tree() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut parent = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    parent.set_icon(Some("parent icon")).unwrap();
+    parent.set_separator(Some(Separator::Before)).unwrap();
+    let mut child = parent.new_child("Child").unwrap();
+    child.set_command(Some("child command")).unwrap();
+    child.set_extended(true).unwrap();
+
+    let snapshot = parent.snapshot().unwrap();
+
+    assert_eq!(snapshot.name, id);
+    assert_eq!(snapshot.icon.as_deref(), Some("parent icon"));
+    assert_eq!(snapshot.separator, Some(Separator::Before));
+    assert_eq!(
+        snapshot.values.get("Subcommands"),
+        Some(&RegValue::Sz(String::new()))
+    );
+    assert_eq!(snapshot.children.len(), 1);
+    assert_eq!(
+        snapshot.children[0].command.as_deref(),
+        Some("child command")
+    );
+    assert!(snapshot.children[0].extended);
+    cleanup_entry(parent);
+}
+
+#[test]
+fn snapshot_unknown_values() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let key = hkcr.open_subkey(entry.path()).unwrap();
+    key.set_value("AppliesTo", &"System.FileName:\"*.rs\"")
+        .unwrap();
+    key.set_value("Flags", &7u32).unwrap();
+
+    let snapshot = entry.snapshot().unwrap();
+
+    assert_eq!(
+        snapshot.values.get("AppliesTo"),
+        Some(&RegValue::Sz("System.FileName:\"*.rs\"".to_string()))
+    );
+    assert_eq!(snapshot.values.get("Flags"), Some(&RegValue::Dword(7)));
+    cleanup_entry(entry);
+}
+
+#[test]
+fn restore_snapshot() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut parent = CtxEntry::new(&id, &ActivationType::Background).unwrap();
+    parent.set_position(Some(MenuPosition::Bottom)).unwrap();
+    let mut child = parent.new_child("Child").unwrap();
+    child.set_label(Some("Child label")).unwrap();
+    hkcr.open_subkey(child.path())
+        .unwrap()
+        .set_value("Flags", &7u32)
+        .unwrap();
+
+    let snapshot = parent.snapshot().unwrap();
+    parent.delete().unwrap();
+    let restored = snapshot.restore(&ActivationType::Background).unwrap();
+
+    assert_eq!(restored.snapshot().unwrap(), snapshot);
+    assert_eq!(
+        snapshot
+            .restore(&ActivationType::Background)
+            .expect_err("Restoring over an existing entry should fail")
+            .kind(),
+        ErrorKind::AlreadyExists
+    );
+    cleanup_entry(restored);
+}
+
+#[test]
+fn restore_snapshot_raw_values() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let icon = RegValue::ExpandSz("%SystemRoot%\\System32\\shell32.dll,-4".to_string());
+    let label = RegValue::ExpandSz("@%SystemRoot%\\System32\\shell32.dll,-8506".to_string());
+    let handler = RegValue::Sz("{1d27f844-3a1f-4410-85ac-14651078412d}".to_string());
+    let flags = RegValue::DwordBigEndian(0x80);
+    let marker = RegValue::Raw {
+        ty: 0,
+        bytes: vec![1, 2, 3],
+    };
+    let size = RegValue::Qword(u64::MAX);
+    entry.set_value("Icon", &icon).unwrap();
+    entry.set_value("MUIVerb", &label).unwrap();
+    entry.set_value("Flags", &flags).unwrap();
+    entry.set_value("Marker", &marker).unwrap();
+    entry.set_value("Size", &size).unwrap();
+    entry.set_command(Some("")).unwrap();
+
+    let (command_key, _) = hkcr
+        .create_subkey(format!("{}\\command", entry.path()))
+        .unwrap();
+    command_key
+        .set_value("DelegateExecute", &handler.as_str().unwrap())
+        .unwrap();
+    let (drop_target, _) = hkcr
+        .create_subkey(format!("{}\\DropTarget", entry.path()))
+        .unwrap();
+    drop_target.set_value("Clsid", &"{clsid}").unwrap();
+
+    let snapshot = entry.snapshot().unwrap();
+    assert_eq!(snapshot.icon.as_deref(), icon.as_str());
+    assert_eq!(
+        snapshot.keys["command"].values.get("DelegateExecute"),
+        Some(&handler)
+    );
+    assert!(snapshot.keys.contains_key("DropTarget"));
+
+    entry.delete().unwrap();
+    let restored = snapshot.restore(&ActivationType::Folder).unwrap();
+
+    assert_eq!(restored.snapshot().unwrap(), snapshot);
+    assert_eq!(restored.get_value("Icon").unwrap(), Some(icon));
+    assert_eq!(restored.get_value("MUIVerb").unwrap(), Some(label));
+    assert_eq!(restored.get_value("Flags").unwrap(), Some(flags));
+    assert_eq!(restored.get_value("Marker").unwrap(), Some(marker));
+    assert_eq!(restored.get_value("Size").unwrap(), Some(size));
+    cleanup_entry(restored);
+}
+
+#[test]
+fn restore_edited_snapshot() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    entry.set_icon(Some("old icon")).unwrap();
+    entry.set_command(Some("old command")).unwrap();
+
+    let mut snapshot = entry.snapshot().unwrap();
+    entry.delete().unwrap();
+    snapshot.icon = Some("new icon".to_string());
+    snapshot.command = None;
+    snapshot.extended = true;
+    let restored = snapshot.restore(&ActivationType::Folder).unwrap();
+
+    assert_eq!(restored.icon().unwrap().as_deref(), Some("new icon"));
+    assert_eq!(restored.command().unwrap(), None);
+    assert!(restored.extended().unwrap());
+    cleanup_entry(restored);
+}
+
+#[test]
+fn restore_snapshot_as_child() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let source = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let snapshot = source.snapshot().unwrap();
+    cleanup_entry(source);
+
+    let parent_id = Uuid::new_v4().to_string();
+    let parent = CtxEntry::new(&parent_id, &ActivationType::Folder).unwrap();
+    let child = snapshot.restore_as_child(&parent).unwrap();
+
+    assert_eq!(child.name_path(), [parent_id, id]);
+    assert_eq!(parent.children().unwrap().len(), 1);
+
+    let err = snapshot
+        .restore_as_child(&parent)
+        .expect_err("Restoring over an existing child should fail");
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    cleanup_entry(parent);
+
+    // A conflict leaves the parent untouched.
+    let leaf_id = Uuid::new_v4().to_string();
+    let mut leaf = CtxEntry::new(&leaf_id, &ActivationType::Folder).unwrap();
+    leaf.set_command(Some("cmd.exe")).unwrap();
+    hkcr.create_subkey(format!("{}\\shell\\{}", leaf.path(), snapshot.name))
+        .unwrap();
+
+    snapshot.restore_as_child(&leaf).unwrap_err();
+    assert_eq!(leaf.get_value("Subcommands").unwrap(), None);
+
+    // So does a restore that fails part way through.
+    let mut broken = snapshot.clone();
+    broken.name = "Broken".to_string();
+    broken.children = vec![snapshot.clone(), snapshot.clone()];
+
+    broken.restore_as_child(&leaf).unwrap_err();
+    assert_eq!(leaf.get_value("Subcommands").unwrap(), None);
+    assert!(
+        CtxEntry::get(&[leaf_id.as_str(), "Broken"], &ActivationType::Folder)
+            .unwrap()
+            .is_none()
+    );
+    cleanup_entry(leaf);
+}
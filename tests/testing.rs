@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+use uuid::Uuid;
+use win_ctx::testing::*;
+use win_ctx::*;
+use winreg::{RegKey, enums::*};
+
+const HKCR: RegKey = RegKey::predef(HKEY_CLASSES_ROOT);
+
+fn names(entries: &[CtxEntry]) -> HashSet<String> {
+    entries.iter().map(|e| e.name().unwrap()).collect()
+}
+
+#[test]
+fn get_all_entries() {
+    for fixture in [Fixture::Windows10, Fixture::Windows11] {
+        let _sandbox = Sandbox::with_fixture(fixture).unwrap();
+
+        for entry_type in [
+            ActivationType::File("*".to_string()),
+            ActivationType::File(".rs".to_string()),
+            ActivationType::Folder,
+            ActivationType::Background,
+        ] {
+            let entries = CtxEntry::get_all_of_type(&entry_type);
+            let expected = fixture.entry_names(&entry_type);
+
+            assert_eq!(
+                entries.keys().map(|k| k.as_str()).collect::<HashSet<_>>(),
+                expected.into_iter().collect(),
+                "{fixture:?} {entry_type:?}"
+            );
+        }
+    }
+
+    assert_eq!(
+        Fixture::Windows10.entry_names(&ActivationType::File("*".to_string())),
+        ["UpdateEncryptionSettingsWork"]
+    );
+    assert_eq!(
+        Fixture::Windows11.entry_names(&ActivationType::File("*".to_string())),
+        ["pintohomefile", "UpdateEncryptionSettingsWork"]
+    );
+    assert_eq!(
+        Fixture::Windows10.entry_names(&ActivationType::Background),
+        ["cmd", "Powershell"]
+    );
+}
+
+#[test]
+fn read_fixture_entries() {
+    let _sandbox = Sandbox::with_fixture(Fixture::Windows10).unwrap();
+
+    let cmd = CtxEntry::get(&["cmd"], &ActivationType::Background)
+        .unwrap()
+        .unwrap();
+    assert_eq!(cmd.label().unwrap(), "@shell32.dll,-8506");
+    assert!(cmd.extended().unwrap());
+    assert_command(&cmd, Some(r#"cmd.exe /s /k pushd "%V""#));
+
+    let find = CtxEntry::get(&["find"], &ActivationType::Folder)
+        .unwrap()
+        .unwrap();
+    assert!(find.disabled().unwrap());
+
+    let registered = ActivationType::all_registered().unwrap();
+    assert_eq!(
+        registered,
+        [
+            ActivationType::Folder,
+            ActivationType::Background,
+            ActivationType::File("*".to_string()),
+        ]
+    );
+
+    let children = CtxEntry::walk_type(&ActivationType::Folder)
+        .map(|item| item.unwrap().entry)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names(&children),
+        ["cmd", "find", "Powershell", "UpdateEncryptionSettings"]
+            .map(String::from)
+            .into()
+    );
+}
+
+#[test]
+fn sandbox_isolates_entries() {
+    let id = Uuid::new_v4().to_string();
+    let sandbox = Sandbox::new().unwrap();
+
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    entry.set_command(Some("cmd.exe")).unwrap();
+    entry.set_extended(true).unwrap();
+
+    assert!(sandbox.root().unwrap().open_subkey(entry.path()).is_ok());
+    assert!(HKCR.open_subkey(entry.path()).is_err());
+    assert_command(&entry, Some("cmd.exe"));
+    assert_value(&entry, "Extended", Some(&RegValue::Sz(String::new())));
+    assert_value(&entry, "Icon", None);
+
+    let path = sandbox.path().to_string();
+    let inner = Sandbox::new().unwrap();
+    assert!(
+        CtxEntry::get(&[&id], &ActivationType::Folder)
+            .unwrap()
+            .is_none()
+    );
+    drop(inner);
+
+    let found = CtxEntry::get(&[&id], &ActivationType::Folder)
+        .unwrap()
+        .unwrap();
+    cleanup_entry(found);
+    assert_command(&entry, None);
+
+    drop(sandbox);
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    assert!(hkcu.open_subkey(path).is_err());
+    assert!(
+        CtxEntry::get(&[&id], &ActivationType::Folder)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn deleted_sandbox_errors() {
+    let sandbox = Sandbox::new().unwrap();
+    RegKey::predef(HKEY_CURRENT_USER)
+        .delete_subkey_all(sandbox.path())
+        .unwrap();
+
+    let err = CtxEntry::new("Basic entry", &ActivationType::Folder).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(ActivationType::all_registered().is_err());
+}
+
+#[test]
+#[should_panic(expected = "unexpected command")]
+fn assert_command_fails() {
+    let _sandbox = Sandbox::with_fixture(Fixture::Windows10).unwrap();
+    let cmd = CtxEntry::get(&["cmd"], &ActivationType::Folder)
+        .unwrap()
+        .unwrap();
+
+    assert_command(&cmd, Some("powershell.exe"));
+}
+
+#[test]
+fn render_fixture_type() {
+    let _sandbox = Sandbox::with_fixture(Fixture::Windows10).unwrap();
+
+    let opts = TreeOptions {
+        ascii: true,
+        hidden: vec![TreeField::Command],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        CtxEntry::render_type(&ActivationType::Background, &opts).unwrap(),
+        "background\n\
+         |- @shell32.dll,-8506 (cmd) [shift]\n\
+         `- @shell32.dll,-8508 (Powershell) [shift]\n"
+    );
+
+    let cmd = CtxEntry::get(&["cmd"], &ActivationType::Folder)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        cmd.render(&TreeOptions::default()).unwrap(),
+        "@shell32.dll,-8506 (cmd) [shift] → cmd.exe /s /k pushd \"%V\"\n"
+    );
+}
@@ -0,0 +1,148 @@
+use common::{Sandbox, cleanup_entry};
+use std::io::ErrorKind;
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+#[test]
+fn copy_between_types() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    entry.set_command(Some("test command")).unwrap();
+    entry.new_child("Child").unwrap();
+
+    let copy = entry
+        .copy_to(
+            Destination::Root(&ActivationType::Background),
+            ConflictPolicy::Fail,
+        )
+        .expect("Failed to copy entry");
+
+    assert_eq!(copy.path(), format!("Directory\\Background\\shell\\{id}"));
+    assert_eq!(copy.snapshot().unwrap(), entry.snapshot().unwrap());
+    cleanup_entry(entry);
+    cleanup_entry(copy);
+}
+
+#[test]
+fn move_between_parents() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let old_parent = CtxEntry::new(&Uuid::new_v4().to_string(), &ActivationType::Folder).unwrap();
+    let new_parent = CtxEntry::new(&Uuid::new_v4().to_string(), &ActivationType::Folder).unwrap();
+    let mut child = old_parent.new_child("Child").unwrap();
+    child.set_icon(Some("test icon")).unwrap();
+    child.new_child("Grandchild").unwrap();
+
+    child
+        .move_to(Destination::Parent(&new_parent), ConflictPolicy::Fail)
+        .expect("Failed to move entry");
+
+    assert_eq!(child.parent().unwrap().unwrap().path(), new_parent.path());
+    assert_eq!(child.icon().unwrap().as_deref(), Some("test icon"));
+    assert!(child.child("Grandchild").unwrap().is_some());
+    assert!(old_parent.children().unwrap().is_empty());
+
+    let old_key = hkcr.open_subkey(old_parent.path()).unwrap();
+    let new_key = hkcr.open_subkey(new_parent.path()).unwrap();
+    assert!(old_key.get_raw_value("Subcommands").is_err());
+    assert!(new_key.get_raw_value("Subcommands").is_ok());
+    cleanup_entry(old_parent);
+    cleanup_entry(new_parent);
+}
+
+#[test]
+fn conflict_policies() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut source = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    source.set_command(Some("source command")).unwrap();
+    source.new_child("Source child").unwrap();
+    let mut target = CtxEntry::new(&id, &ActivationType::Background).unwrap();
+    target.set_icon(Some("target icon")).unwrap();
+    target.new_child("Target child").unwrap();
+    let dest = Destination::Root(&ActivationType::Background);
+
+    let err = source
+        .copy_to(dest, ConflictPolicy::Fail)
+        .expect_err("Conflicting copy should fail");
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+
+    let merged = source.copy_to(dest, ConflictPolicy::Merge).unwrap();
+    assert_eq!(merged.command().unwrap().as_deref(), Some("source command"));
+    assert_eq!(merged.icon().unwrap().as_deref(), Some("target icon"));
+    assert_eq!(merged.children().unwrap().len(), 2);
+
+    // A failed overwrite keeps the existing entry.
+    let blocker = CtxEntry::new(&format!("~backup-{id}"), &ActivationType::Background).unwrap();
+    let before = merged.snapshot().unwrap();
+    source
+        .copy_to(dest, ConflictPolicy::Overwrite)
+        .expect_err("Clashing backup name should fail");
+    assert_eq!(merged.snapshot().unwrap(), before);
+    assert!(
+        CtxEntry::get(&[format!("~copy-{id}")], &ActivationType::Background)
+            .unwrap()
+            .is_none()
+    );
+    cleanup_entry(blocker);
+
+    let overwritten = source.copy_to(dest, ConflictPolicy::Overwrite).unwrap();
+    assert_eq!(overwritten.snapshot().unwrap(), source.snapshot().unwrap());
+    cleanup_entry(source);
+    cleanup_entry(overwritten);
+}
+
+#[test]
+fn failed_copy_keeps_parent() {
+    let _sandbox = Sandbox::new().unwrap();
+    let source = CtxEntry::new("Target", &ActivationType::Folder).unwrap();
+    let mut parent = CtxEntry::new(&Uuid::new_v4().to_string(), &ActivationType::Folder).unwrap();
+    parent.new_child("Target").unwrap();
+    parent.new_child("~backup-Target").unwrap();
+    parent.delete_value("Subcommands").unwrap();
+
+    source
+        .copy_to(Destination::Parent(&parent), ConflictPolicy::Overwrite)
+        .expect_err("Clashing backup name should fail");
+
+    assert_eq!(parent.get_value("Subcommands").unwrap(), None);
+    cleanup_entry(source);
+    cleanup_entry(parent);
+}
+
+#[test]
+fn move_into_itself() {
+    let _sandbox = Sandbox::new().unwrap();
+    let mut parent = CtxEntry::new(&Uuid::new_v4().to_string(), &ActivationType::Folder).unwrap();
+    let child = parent.new_child("Child").unwrap();
+
+    let err = parent
+        .move_to(Destination::Parent(&child), ConflictPolicy::Merge)
+        .expect_err("Moving an entry into its child should fail");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    // Names and extensions are compared ignoring case.
+    let shouting = [parent.name().unwrap().to_uppercase(), "CHILD".to_string()];
+    let child = CtxEntry::get(&shouting, &ActivationType::Folder)
+        .unwrap()
+        .unwrap();
+    let err = parent
+        .move_to(Destination::Parent(&child), ConflictPolicy::Merge)
+        .expect_err("Moving an entry into its child should fail");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    cleanup_entry(parent);
+
+    let entry = CtxEntry::new("Entry", &ActivationType::File(".rs".to_string())).unwrap();
+    let err = entry
+        .copy_to(
+            Destination::Root(&ActivationType::File(".RS".to_string())),
+            ConflictPolicy::Overwrite,
+        )
+        .expect_err("Copying an entry onto itself should fail");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(entry.name().is_ok());
+    cleanup_entry(entry);
+}
@@ -0,0 +1,61 @@
+use common::{Sandbox, cleanup_entry};
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+#[test]
+fn validate_valid_entry() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let exe = std::env::current_exe().unwrap();
+
+    let built = CtxEntry::builder(&id, &ActivationType::Background)
+        .child(|c| {
+            c.name("Launch")
+                .command(&format!("\"{}\" \"%V\"", exe.display()))
+        })
+        .build()
+        .unwrap();
+
+    assert_eq!(built.entry.validate().unwrap(), []);
+    cleanup_entry(built.entry);
+}
+
+#[test]
+fn validate_reports_problems() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+
+    let mut built = CtxEntry::builder(&id, &ActivationType::Background)
+        .child(|c| c.name("Launch").command(r#""C:\missing\tool.exe" "%1""#))
+        .child(|c| c.name("Other").label("launch"))
+        .build()
+        .unwrap();
+    built.entry.delete_value("Subcommands").unwrap();
+
+    let diagnostics = built.entry.validate().unwrap();
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.kind, d.address.name_path.join("\\")))
+        .collect();
+
+    assert_eq!(
+        found,
+        [
+            (DiagnosticKind::MissingSubcommands, id.clone()),
+            (DiagnosticKind::DuplicateLabel, format!("{id}\\Other")),
+            (DiagnosticKind::MissingExecutable, format!("{id}\\Launch")),
+            (DiagnosticKind::WrongPlaceholder, format!("{id}\\Launch")),
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.hint.is_some()));
+
+    let without_executables = Validator::new()
+        .check_executables(false)
+        .validate_entry(&built.entry)
+        .unwrap();
+    assert_eq!(without_executables.len(), 3);
+
+    cleanup_entry(built.entry);
+}
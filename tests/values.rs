@@ -0,0 +1,116 @@
+use common::{Sandbox, cleanup_entry};
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+#[test]
+fn get_set_and_delete_values() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+
+    let values = [
+        ("Sz", RegValue::Sz("text".to_string())),
+        ("ExpandSz", RegValue::ExpandSz("%SystemRoot%".to_string())),
+        (
+            "MultiSz",
+            RegValue::MultiSz(vec!["a".to_string(), "b".to_string()]),
+        ),
+        ("Dword", RegValue::Dword(1)),
+        ("Qword", RegValue::Qword(u64::MAX)),
+        ("Binary", RegValue::Binary(vec![1, 2, 3])),
+        ("None", RegValue::None),
+    ];
+
+    for (name, value) in &values {
+        entry.set_value(name, value).expect("Failed to set value");
+        assert_eq!(entry.get_value(name).unwrap().as_ref(), Some(value));
+    }
+
+    let key = hkcr.open_subkey(entry.path()).unwrap();
+    assert_eq!(key.get_value::<u32, _>("Dword").unwrap(), 1);
+
+    let all = entry.values().unwrap();
+    for (name, value) in &values {
+        assert_eq!(all.get(*name), Some(value));
+    }
+
+    entry.delete_value("Dword").expect("Failed to delete value");
+    entry
+        .delete_value("Dword")
+        .expect("Duplicate delete should be ok");
+    assert_eq!(entry.get_value("Dword").unwrap(), None);
+    cleanup_entry(entry);
+}
+
+#[test]
+fn values_include_known_values() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    entry.set_icon(Some("test icon")).unwrap();
+
+    let icon = entry.get_value("icon").unwrap();
+    assert_eq!(icon.as_ref().and_then(RegValue::as_str), Some("test icon"));
+    assert!(entry.values().unwrap().contains_key("Icon"));
+
+    entry
+        .set_value("Icon", &RegValue::ExpandSz("%icon%".to_string()))
+        .unwrap();
+    assert_eq!(entry.icon().unwrap().as_deref(), Some("%icon%"));
+    cleanup_entry(entry);
+}
+
+#[test]
+fn tolerant_reads() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    entry.set_value("Extended", &RegValue::Dword(1)).unwrap();
+    entry.set_value("SeparatorBefore", &RegValue::None).unwrap();
+    entry
+        .set_value("Position", &RegValue::Sz("bottom".to_string()))
+        .unwrap();
+
+    assert!(entry.extended().unwrap());
+    assert_eq!(entry.separator().unwrap(), Some(Separator::Before));
+    assert_eq!(entry.position().unwrap(), Some(MenuPosition::Bottom));
+
+    let props = entry.properties().unwrap();
+    assert!(props.extended);
+    assert_eq!(props.separator, Some(Separator::Before));
+    assert_eq!(props.position, Some(MenuPosition::Bottom));
+    cleanup_entry(entry);
+}
+
+#[test]
+fn warn_and_normalize() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let mut entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    entry.set_value("Extended", &RegValue::Dword(1)).unwrap();
+    entry
+        .set_value("Position", &RegValue::Sz("top".to_string()))
+        .unwrap();
+    entry.set_value("Icon", &RegValue::Dword(2)).unwrap();
+
+    let warnings = entry.value_warnings().unwrap();
+    let names: Vec<_> = warnings.iter().map(|w| w.name.as_str()).collect();
+    assert_eq!(names, ["Extended", "Icon", "Position"]);
+
+    let fixed = entry.normalize().expect("Failed to normalize");
+    assert_eq!(fixed, warnings);
+
+    let key = hkcr.open_subkey(entry.path()).unwrap();
+    assert_eq!(key.get_value::<String, _>("Extended").unwrap(), "");
+    assert_eq!(key.get_value::<String, _>("Position").unwrap(), "Top");
+    assert_eq!(entry.get_value("Icon").unwrap(), Some(RegValue::Dword(2)));
+
+    let remaining = entry.value_warnings().unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].canonical, None);
+    cleanup_entry(entry);
+}
@@ -0,0 +1,96 @@
+use common::{Sandbox, cleanup_entry};
+use uuid::Uuid;
+use win_ctx::*;
+
+mod common;
+
+fn walk_paths(walk: Walk) -> Vec<(String, usize)> {
+    walk.map(|item| {
+        let item = item.unwrap();
+        (item.path().join("/"), item.depth)
+    })
+    .collect()
+}
+
+#[test]
+fn walk_entry_depth_first() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let b = entry.new_child("b").unwrap();
+    b.new_child("b1").unwrap();
+    entry.new_child("a").unwrap();
+
+    assert_eq!(
+        walk_paths(entry.walk()),
+        [
+            (id.clone(), 0),
+            (format!("{id}/a"), 1),
+            (format!("{id}/b"), 1),
+            (format!("{id}/b/b1"), 2),
+        ]
+    );
+
+    cleanup_entry(entry);
+}
+
+#[test]
+fn walk_max_depth() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Folder).unwrap();
+    let child = entry.new_child("Child").unwrap();
+    child.new_child("Grandchild").unwrap();
+
+    assert_eq!(
+        walk_paths(entry.walk().max_depth(1)),
+        [(id.clone(), 0), (format!("{id}/Child"), 1)]
+    );
+
+    cleanup_entry(entry);
+}
+
+#[test]
+fn walk_skip_children() {
+    let _sandbox = Sandbox::new().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry = CtxEntry::new(&id, &ActivationType::Background).unwrap();
+    let skipped = entry.new_child("Skipped").unwrap();
+    skipped.new_child("Hidden").unwrap();
+    entry.new_child("Visible").unwrap();
+
+    let mut walk = entry.walk();
+    let mut names = Vec::new();
+
+    while let Some(item) = walk.next() {
+        let item = item.unwrap();
+        let name = item.entry.name().unwrap();
+
+        if name == "Skipped" {
+            walk.skip_children();
+        }
+
+        names.push(name);
+    }
+
+    assert_eq!(names, [id.as_str(), "Skipped", "Visible"]);
+    cleanup_entry(entry);
+}
+
+#[test]
+fn walk_activation_type() {
+    let sandbox = Sandbox::new().unwrap();
+    let hkcr = sandbox.root().unwrap();
+    let id = Uuid::new_v4().to_string();
+    let entry_type = ActivationType::File(format!(".{id}"));
+    let entry = CtxEntry::new("Root", &entry_type).unwrap();
+    entry.new_child("Child").unwrap();
+
+    assert_eq!(
+        walk_paths(CtxEntry::walk_type(&entry_type)),
+        [("Root".to_string(), 0), ("Root/Child".to_string(), 1)]
+    );
+
+    cleanup_entry(entry);
+    hkcr.delete_subkey_all(format!(".{id}")).unwrap();
+}